@@ -0,0 +1,81 @@
+//! GL calls that read the same on WebGL 1 and WebGL 2. web-sys gives the two contexts
+//! unrelated types with identically named methods, so `gl_helpers!` stamps the helpers
+//! out once per context type.
+
+macro_rules! gl_helpers {
+    ($gl:ty) => {
+        fn buffer_target(data: &$crate::backend::BufferData) -> u32 {
+            match data {
+                $crate::backend::BufferData::Vertices(_) => <$gl>::ARRAY_BUFFER,
+                $crate::backend::BufferData::Indices(_) => <$gl>::ELEMENT_ARRAY_BUFFER,
+            }
+        }
+
+        /// Fill the buffer bound at `target` with `data`
+        fn upload_buffer(context: &$gl, target: u32, data: $crate::backend::BufferData, usage: $crate::backend::BufferUsage) {
+            let usage = match usage {
+                $crate::backend::BufferUsage::Static => <$gl>::STATIC_DRAW,
+                $crate::backend::BufferUsage::Dynamic => <$gl>::DYNAMIC_DRAW,
+            };
+            unsafe {
+                match data {
+                    $crate::backend::BufferData::Vertices(vertices) => {
+                        let array = js_sys::Float32Array::view(vertices);
+                        context.buffer_data_with_array_buffer_view(target, &array, usage);
+                    }
+                    $crate::backend::BufferData::Indices(indices) => {
+                        let array = js_sys::Uint16Array::view(indices);
+                        context.buffer_data_with_array_buffer_view(target, &array, usage);
+                    }
+                }
+            }
+        }
+
+        fn compile_shader(context: &$gl, shader_type: u32, source: &str) -> Result<web_sys::WebGlShader, String> {
+            let shader = context
+                .create_shader(shader_type)
+                .ok_or_else(|| String::from("Unable to create shader object"))?;
+            context.shader_source(&shader, source);
+            context.compile_shader(&shader);
+
+            if context
+                .get_shader_parameter(&shader, <$gl>::COMPILE_STATUS)
+                .as_bool()
+                .unwrap_or(false)
+            {
+                Ok(shader)
+            } else {
+                Err(context
+                    .get_shader_info_log(&shader)
+                    .unwrap_or_else(|| String::from("Unknown error creating shader")))
+            }
+        }
+
+        /// Compile both stages and link them into a program
+        fn create_program(context: &$gl, vertex_source: &str, fragment_source: &str) -> Result<web_sys::WebGlProgram, String> {
+            let vert_shader = compile_shader(context, <$gl>::VERTEX_SHADER, vertex_source)?;
+            let frag_shader = compile_shader(context, <$gl>::FRAGMENT_SHADER, fragment_source)?;
+            let program = context
+                .create_program()
+                .ok_or_else(|| String::from("Unable to create shader object"))?;
+
+            context.attach_shader(&program, &vert_shader);
+            context.attach_shader(&program, &frag_shader);
+            context.link_program(&program);
+
+            if context
+                .get_program_parameter(&program, <$gl>::LINK_STATUS)
+                .as_bool()
+                .unwrap_or(false)
+            {
+                Ok(program)
+            } else {
+                Err(context
+                    .get_program_info_log(&program)
+                    .unwrap_or_else(|| String::from("Unknown error creating program object")))
+            }
+        }
+    };
+}
+
+pub(crate) use gl_helpers;
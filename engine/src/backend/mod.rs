@@ -0,0 +1,131 @@
+//! Everything the engine asks of the GPU, behind one trait. Shapes and renderers only
+//! talk to a `GraphicsBackend`, so the same scene can be drawn by WebGL or by anything
+//! else that implements it.
+
+mod gl;
+pub mod recording;
+pub mod software;
+pub mod webgl;
+pub mod webgl2;
+
+use crate::shapes::geometry::Primitive;
+
+pub use crate::shaders::ShaderKind;
+pub use crate::texture::TextureSource;
+pub use recording::RecordingBackend;
+pub use software::SoftwareBackend;
+pub use webgl::WebGlBackend;
+pub use webgl2::WebGl2Backend;
+
+/// A vertex or index buffer owned by a backend
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BufferHandle(pub usize);
+
+/// A linked shader program owned by a backend
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ProgramHandle(pub usize);
+
+/// A 2D texture owned by a backend
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureHandle(pub usize);
+
+/// How often a buffer's contents are expected to change
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferUsage {
+    Static,  // Uploaded once, drawn many times
+    Dynamic, // Rewritten most frames
+}
+
+/// Contents of a buffer: vertex attributes as floats, or 16-bit indices
+#[derive(Clone, Copy, Debug)]
+pub enum BufferData<'a> {
+    Vertices(&'a [f32]),
+    Indices(&'a [u16]),
+}
+
+/// A uniform value, named after the GLSL type it fills
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Uniform {
+    Float(f32),
+    Int(i32),
+    Bool(bool),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Mat3([f32; 9]),  // Column-major
+    Mat4([f32; 16]), // Column-major
+    Texture(TextureHandle),
+}
+
+/// Where a shader attribute reads from: `size` floats every `stride` bytes, starting at `offset`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VertexAttribute<'a> {
+    pub name: &'a str,
+    pub buffer: BufferHandle,
+    pub size: i32,
+    pub stride: i32,  // Bytes
+    pub offset: i32,  // Bytes
+    pub divisor: u32, // 0 to advance every vertex, n to advance every n instances
+}
+
+/// One draw: `count` vertices (or indices, when `indices` is set) starting at `first`,
+/// repeated for each of `instances` instances
+#[derive(Clone, Copy, Debug)]
+pub struct DrawCall<'a> {
+    pub program: ProgramHandle,
+    pub attributes: &'a [VertexAttribute<'a>],
+    pub indices: Option<BufferHandle>,
+    pub primitive: Primitive,
+    pub first: usize,
+    pub count: usize,
+    pub instances: usize,
+}
+
+/// Buffers, programs, uniforms, draws and the bits of pipeline state the engine uses.
+///
+/// Methods take `&self` so renderers can draw through a shared reference; backends keep
+/// their own bookkeeping behind interior mutability. Blending, when enabled, is always
+/// straight alpha (`SRC_ALPHA`, `ONE_MINUS_SRC_ALPHA`).
+pub trait GraphicsBackend {
+    fn create_buffer(&self, data: BufferData, usage: BufferUsage) -> Result<BufferHandle, String>;
+    /// Replace a buffer's contents, resizing it if needed
+    fn update_buffer(&self, buffer: BufferHandle, data: BufferData);
+    fn delete_buffer(&self, buffer: BufferHandle);
+
+    fn create_program(&self, shader: ShaderKind) -> Result<ProgramHandle, String>;
+    fn delete_program(&self, program: ProgramHandle);
+    /// Set a uniform on `program`; names the program does not use are ignored
+    fn set_uniform(&self, program: ProgramHandle, name: &str, value: Uniform);
+
+    /// Upload a texture, with mipmaps when the backend supports them for its size
+    fn create_texture(&self, source: &TextureSource) -> Result<TextureHandle, String>;
+    fn delete_texture(&self, texture: TextureHandle);
+
+    fn draw(&self, call: &DrawCall);
+    /// Whether `draw` honours `instances` and attribute divisors; callers must draw
+    /// instance by instance when it does not
+    fn supports_instancing(&self) -> bool;
+
+    fn clear(&self, color: [f32; 4], depth: bool);
+    fn set_viewport(&self, width: u32, height: u32);
+    fn set_depth_test(&self, enabled: bool);
+    fn set_depth_write(&self, enabled: bool);
+    fn set_blending(&self, enabled: bool);
+
+    /// The viewport's pixels as RGBA bytes, top row first
+    fn read_pixels(&self) -> Vec<u8>;
+
+    /// Short name for diagnostics, such as `"webgl2"`
+    fn name(&self) -> &'static str;
+}
+
+/// Put `item` in the first free slot of `slots` and return its index, so backends can
+/// reuse the handles of deleted resources
+pub(crate) fn insert<T>(slots: &mut Vec<Option<T>>, item: T) -> usize {
+    if let Some(index) = slots.iter().position(Option::is_none) {
+        slots[index] = Some(item);
+        index
+    } else {
+        slots.push(Some(item));
+        slots.len() - 1
+    }
+}
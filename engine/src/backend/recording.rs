@@ -0,0 +1,248 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use crate::shaders::ShaderKind;
+use crate::shapes::geometry::Primitive;
+use crate::texture::TextureSource;
+use super::{BufferData, BufferHandle, BufferUsage, DrawCall, GraphicsBackend, ProgramHandle, TextureHandle, Uniform};
+
+/// One call made on a `RecordingBackend`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    CreateBuffer { buffer: BufferHandle, usage: BufferUsage, contents: BufferContents },
+    UpdateBuffer { buffer: BufferHandle, contents: BufferContents },
+    DeleteBuffer(BufferHandle),
+    CreateProgram { program: ProgramHandle, shader: ShaderKind },
+    DeleteProgram(ProgramHandle),
+    SetUniform { program: ProgramHandle, name: String, value: Uniform },
+    CreateTexture { texture: TextureHandle, width: u32, height: u32 }, // Zero sizes for browser images
+    DeleteTexture(TextureHandle),
+    Draw(DrawCommand),
+    Clear { color: [f32; 4], depth: bool },
+    SetViewport { width: u32, height: u32 },
+    SetDepthTest(bool),
+    SetDepthWrite(bool),
+    SetBlending(bool),
+}
+
+/// What was uploaded to a buffer, kept in full so tests can check the data itself
+#[derive(Clone, Debug, PartialEq)]
+pub enum BufferContents {
+    Vertices(Vec<f32>),
+    Indices(Vec<u16>),
+}
+
+/// A recorded `draw`, with the program's shader looked up so tests need not track handles
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawCommand {
+    pub program: ProgramHandle,
+    pub shader: ShaderKind,
+    pub primitive: Primitive,
+    pub first: usize,
+    pub count: usize,
+    pub instances: usize,
+    pub indexed: bool,
+    pub attributes: Vec<String>,
+}
+
+/// Pipeline state as of the last recorded call
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderState {
+    pub depth_test: bool,
+    pub depth_write: bool,
+    pub blending: bool,
+    pub viewport: (u32, u32),
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        // GL's initial state
+        Self { depth_test: false, depth_write: true, blending: false, viewport: (0, 0) }
+    }
+}
+
+#[derive(Default)]
+struct Recording {
+    commands: Vec<Command>,
+    state: RenderState,
+}
+
+/// Shared view of a `RecordingBackend`'s log that stays usable after the backend has
+/// been handed to a `GraphicsEngine`
+#[derive(Clone, Default)]
+pub struct CommandLog(Rc<RefCell<Recording>>);
+
+impl CommandLog {
+    pub fn commands(&self) -> Vec<Command> {
+        self.0.borrow().commands.clone()
+    }
+
+    /// Just the draws, in order
+    pub fn draws(&self) -> Vec<DrawCommand> {
+        self.0
+            .borrow()
+            .commands
+            .iter()
+            .filter_map(|command| match command {
+                Command::Draw(draw) => Some(draw.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn state(&self) -> RenderState {
+        self.0.borrow().state
+    }
+
+    /// Forget every command so far, keeping the current state
+    pub fn clear(&self) {
+        self.0.borrow_mut().commands.clear();
+    }
+
+    fn push(&self, command: Command) {
+        let mut recording = self.0.borrow_mut();
+        let state = &mut recording.state;
+        match command {
+            Command::SetDepthTest(enabled) => state.depth_test = enabled,
+            Command::SetDepthWrite(enabled) => state.depth_write = enabled,
+            Command::SetBlending(enabled) => state.blending = enabled,
+            Command::SetViewport { width, height } => state.viewport = (width, height),
+            _ => {}
+        }
+        recording.commands.push(command);
+    }
+}
+
+/// `GraphicsBackend` that draws nothing and logs every call, for tests that check what
+/// the engine asks of the GPU rather than what comes out
+#[derive(Default)]
+pub struct RecordingBackend {
+    log: CommandLog,
+    programs: RefCell<Vec<ShaderKind>>,
+    next_buffer: Cell<usize>,
+    next_texture: Cell<usize>,
+    without_instancing: bool,
+}
+
+impl RecordingBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A backend that reports no instancing, like WebGL 1 without `ANGLE_instanced_arrays`
+    pub fn without_instancing() -> Self {
+        Self { without_instancing: true, ..Self::default() }
+    }
+
+    pub fn log(&self) -> CommandLog {
+        self.log.clone()
+    }
+}
+
+fn contents(data: BufferData) -> BufferContents {
+    match data {
+        BufferData::Vertices(vertices) => BufferContents::Vertices(vertices.to_vec()),
+        BufferData::Indices(indices) => BufferContents::Indices(indices.to_vec()),
+    }
+}
+
+/// Hand out handles in sequence, never reusing one, so the log is unambiguous
+fn next_handle(counter: &Cell<usize>) -> usize {
+    let handle = counter.get();
+    counter.set(handle + 1);
+    handle
+}
+
+impl GraphicsBackend for RecordingBackend {
+    fn create_buffer(&self, data: BufferData, usage: BufferUsage) -> Result<BufferHandle, String> {
+        let buffer = BufferHandle(next_handle(&self.next_buffer));
+        self.log.push(Command::CreateBuffer { buffer, usage, contents: contents(data) });
+        Ok(buffer)
+    }
+
+    fn update_buffer(&self, buffer: BufferHandle, data: BufferData) {
+        self.log.push(Command::UpdateBuffer { buffer, contents: contents(data) });
+    }
+
+    fn delete_buffer(&self, buffer: BufferHandle) {
+        self.log.push(Command::DeleteBuffer(buffer));
+    }
+
+    fn create_program(&self, shader: ShaderKind) -> Result<ProgramHandle, String> {
+        let mut programs = self.programs.borrow_mut();
+        programs.push(shader);
+        let program = ProgramHandle(programs.len() - 1);
+        self.log.push(Command::CreateProgram { program, shader });
+        Ok(program)
+    }
+
+    fn delete_program(&self, program: ProgramHandle) {
+        self.log.push(Command::DeleteProgram(program));
+    }
+
+    fn set_uniform(&self, program: ProgramHandle, name: &str, value: Uniform) {
+        self.log.push(Command::SetUniform { program, name: name.to_string(), value });
+    }
+
+    fn create_texture(&self, source: &TextureSource) -> Result<TextureHandle, String> {
+        source.validate()?;
+        let (width, height) = match source {
+            TextureSource::Rgba { width, height, .. } => (*width, *height),
+            _ => (0, 0),
+        };
+        let texture = TextureHandle(next_handle(&self.next_texture));
+        self.log.push(Command::CreateTexture { texture, width, height });
+        Ok(texture)
+    }
+
+    fn delete_texture(&self, texture: TextureHandle) {
+        self.log.push(Command::DeleteTexture(texture));
+    }
+
+    fn draw(&self, call: &DrawCall) {
+        let Some(&shader) = self.programs.borrow().get(call.program.0) else { return };
+        self.log.push(Command::Draw(DrawCommand {
+            program: call.program,
+            shader,
+            primitive: call.primitive,
+            first: call.first,
+            count: call.count,
+            instances: call.instances,
+            indexed: call.indices.is_some(),
+            attributes: call.attributes.iter().map(|attribute| attribute.name.to_string()).collect(),
+        }));
+    }
+
+    fn supports_instancing(&self) -> bool {
+        !self.without_instancing
+    }
+
+    fn clear(&self, color: [f32; 4], depth: bool) {
+        self.log.push(Command::Clear { color, depth });
+    }
+
+    fn set_viewport(&self, width: u32, height: u32) {
+        self.log.push(Command::SetViewport { width, height });
+    }
+
+    fn set_depth_test(&self, enabled: bool) {
+        self.log.push(Command::SetDepthTest(enabled));
+    }
+
+    fn set_depth_write(&self, enabled: bool) {
+        self.log.push(Command::SetDepthWrite(enabled));
+    }
+
+    fn set_blending(&self, enabled: bool) {
+        self.log.push(Command::SetBlending(enabled));
+    }
+
+    /// Nothing is drawn, so the viewport reads back as transparent black
+    fn read_pixels(&self) -> Vec<u8> {
+        let (width, height) = self.log.state().viewport;
+        vec![0; width as usize * height as usize * 4]
+    }
+
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+}
@@ -0,0 +1,274 @@
+//! A CPU rasterizer behind `GraphicsBackend`, for rendering without a GPU or a browser.
+//! Every `ShaderKind` has a Rust port in `shaders`, so the same scene comes out close to
+//! what WebGL draws.
+
+pub mod raster;
+pub mod shaders;
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use crate::math::Vec4;
+use crate::shaders::ShaderKind;
+use crate::shapes::geometry::Primitive;
+use crate::texture::TextureSource;
+use super::{insert, BufferData, BufferHandle, BufferUsage, DrawCall, GraphicsBackend, ProgramHandle, TextureHandle, Uniform};
+use raster::Viewport;
+use shaders::{Shader, Texture, VertexOut, MAX_ATTRIBUTES};
+
+enum Buffer {
+    Vertices(Vec<f32>),
+    Indices(Vec<u16>),
+}
+
+struct Program {
+    kind: ShaderKind,
+    uniforms: HashMap<String, Uniform>,
+}
+
+/// `GraphicsBackend` drawing into an RGBA framebuffer in memory. The framebuffer is
+/// resized to match the viewport.
+pub struct SoftwareBackend {
+    viewport: Cell<Viewport>,
+    color: RefCell<Vec<u8>>, // RGBA, top row first, like `read_pixels`
+    depth: RefCell<Vec<f32>>,
+    buffers: RefCell<Vec<Option<Buffer>>>,
+    programs: RefCell<Vec<Option<Program>>>,
+    textures: RefCell<Vec<Option<Texture>>>,
+    depth_test: Cell<bool>,
+    depth_write: Cell<bool>,
+    blending: Cell<bool>,
+}
+
+impl SoftwareBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        let backend = Self {
+            viewport: Cell::new(Viewport { width: 0, height: 0 }),
+            color: RefCell::new(Vec::new()),
+            depth: RefCell::new(Vec::new()),
+            buffers: RefCell::new(Vec::new()),
+            programs: RefCell::new(Vec::new()),
+            textures: RefCell::new(Vec::new()),
+            depth_test: Cell::new(false),
+            depth_write: Cell::new(true),
+            blending: Cell::new(false),
+        };
+        backend.set_viewport(width, height);
+        backend
+    }
+
+    /// Fetch attribute `name` of vertex `index` in `instance`, filled out to a `vec4` with (0, 0, 0, 1)
+    fn fetch(&self, buffers: &[Option<Buffer>], call: &DrawCall, name: &str, index: usize, instance: usize) -> Vec4 {
+        let mut value = [0.0, 0.0, 0.0, 1.0];
+        let attribute = call.attributes.iter().find(|attribute| attribute.name == name);
+        let data = attribute.and_then(|attribute| match buffers.get(attribute.buffer.0) {
+            Some(Some(Buffer::Vertices(data))) => Some((attribute, data)),
+            _ => None,
+        });
+
+        if let Some((attribute, data)) = data {
+            let element = match attribute.divisor {
+                0 => index,
+                divisor => instance / divisor as usize,
+            };
+            let start = (attribute.offset as usize + element * attribute.stride as usize) / 4;
+            let size = attribute.size.clamp(0, 4) as usize;
+            if let Some(floats) = data.get(start..start + size) {
+                value[..size].copy_from_slice(floats);
+            }
+        }
+        Vec4::from(value)
+    }
+}
+
+fn to_buffer(data: BufferData) -> Buffer {
+    match data {
+        BufferData::Vertices(vertices) => Buffer::Vertices(vertices.to_vec()),
+        BufferData::Indices(indices) => Buffer::Indices(indices.to_vec()),
+    }
+}
+
+impl GraphicsBackend for SoftwareBackend {
+    fn create_buffer(&self, data: BufferData, _usage: BufferUsage) -> Result<BufferHandle, String> {
+        Ok(BufferHandle(insert(&mut self.buffers.borrow_mut(), to_buffer(data))))
+    }
+
+    fn update_buffer(&self, buffer: BufferHandle, data: BufferData) {
+        if let Some(slot @ Some(_)) = self.buffers.borrow_mut().get_mut(buffer.0) {
+            *slot = Some(to_buffer(data));
+        }
+    }
+
+    fn delete_buffer(&self, buffer: BufferHandle) {
+        if let Some(slot) = self.buffers.borrow_mut().get_mut(buffer.0) {
+            *slot = None;
+        }
+    }
+
+    fn create_program(&self, shader: ShaderKind) -> Result<ProgramHandle, String> {
+        let program = Program { kind: shader, uniforms: HashMap::new() };
+        Ok(ProgramHandle(insert(&mut self.programs.borrow_mut(), program)))
+    }
+
+    fn delete_program(&self, program: ProgramHandle) {
+        if let Some(slot) = self.programs.borrow_mut().get_mut(program.0) {
+            *slot = None;
+        }
+    }
+
+    fn set_uniform(&self, program: ProgramHandle, name: &str, value: Uniform) {
+        if let Some(Some(program)) = self.programs.borrow_mut().get_mut(program.0) {
+            program.uniforms.insert(name.to_string(), value);
+        }
+    }
+
+    fn create_texture(&self, source: &TextureSource) -> Result<TextureHandle, String> {
+        source.validate()?;
+        let TextureSource::Rgba { width, height, pixels } = source else {
+            return Err("The software backend only takes raw RGBA textures".to_string());
+        };
+        let texture = Texture { width: *width, height: *height, pixels: pixels.to_vec() };
+        Ok(TextureHandle(insert(&mut self.textures.borrow_mut(), texture)))
+    }
+
+    fn delete_texture(&self, texture: TextureHandle) {
+        if let Some(slot) = self.textures.borrow_mut().get_mut(texture.0) {
+            *slot = None;
+        }
+    }
+
+    fn draw(&self, call: &DrawCall) {
+        let programs = self.programs.borrow();
+        let Some(program) = programs.get(call.program.0).and_then(Option::as_ref) else { return };
+        let textures = self.textures.borrow();
+        let shader = Shader::bind(program.kind, &program.uniforms, &textures);
+        let buffers = self.buffers.borrow();
+
+        let indices: Vec<usize> = match call.indices {
+            Some(handle) => match buffers.get(handle.0) {
+                Some(Some(Buffer::Indices(indices))) => indices
+                    .iter()
+                    .skip(call.first)
+                    .take(call.count)
+                    .map(|&index| index as usize)
+                    .collect(),
+                _ => return,
+            },
+            None => (call.first..call.first + call.count).collect(),
+        };
+
+        let viewport = self.viewport.get();
+        let mut color = self.color.borrow_mut();
+        let mut depth = self.depth.borrow_mut();
+        let (depth_test, depth_write, blending) = (self.depth_test.get(), self.depth_write.get(), self.blending.get());
+
+        let mut fragment = |x: usize, y: usize, z: f32, varyings: &shaders::Varyings, point_coord| {
+            let pixel = y * viewport.width + x;
+            if depth_test && z >= depth[pixel] {
+                return;
+            }
+
+            let source = shader.fragment(varyings, point_coord).to_array().map(|c| c.clamp(0.0, 1.0));
+            let target = &mut color[pixel * 4..pixel * 4 + 4];
+            for (channel, value) in target.iter_mut().zip(source) {
+                // SRC_ALPHA, ONE_MINUS_SRC_ALPHA on every channel, alpha included
+                let value = if blending {
+                    value * source[3] + (*channel as f32 / 255.0) * (1.0 - source[3])
+                } else {
+                    value
+                };
+                *channel = (value * 255.0).round() as u8;
+            }
+
+            // As in GL, depth is only written while the depth test is on
+            if depth_test && depth_write {
+                depth[pixel] = z;
+            }
+        };
+
+        let vertex_count = indices.iter().max().map_or(0, |max| max + 1);
+        let names = shader.attributes();
+        for instance in 0..call.instances {
+            // Shade each vertex once, however many primitives share it
+            let mut shaded: Vec<Option<VertexOut>> = vec![None; vertex_count];
+            let vertices: Vec<VertexOut> = indices
+                .iter()
+                .map(|&index| {
+                    *shaded[index].get_or_insert_with(|| {
+                        let mut attributes = [Vec4::ZERO; MAX_ATTRIBUTES];
+                        for (attribute, name) in attributes.iter_mut().zip(names) {
+                            *attribute = self.fetch(&buffers, call, name, index, instance);
+                        }
+                        shader.vertex(&attributes)
+                    })
+                })
+                .collect();
+
+            match call.primitive {
+                Primitive::Triangles => {
+                    for triangle in vertices.chunks_exact(3) {
+                        raster::triangle(viewport, [&triangle[0], &triangle[1], &triangle[2]], &mut fragment);
+                    }
+                }
+                Primitive::Lines => {
+                    for line in vertices.chunks_exact(2) {
+                        raster::line(viewport, &line[0], &line[1], &mut fragment);
+                    }
+                }
+                Primitive::LineStrip | Primitive::LineLoop => {
+                    for pair in vertices.windows(2) {
+                        raster::line(viewport, &pair[0], &pair[1], &mut fragment);
+                    }
+                    if call.primitive == Primitive::LineLoop && vertices.len() > 2 {
+                        raster::line(viewport, &vertices[vertices.len() - 1], &vertices[0], &mut fragment);
+                    }
+                }
+                Primitive::Points => {
+                    for vertex in &vertices {
+                        raster::point(viewport, vertex, &mut fragment);
+                    }
+                }
+            }
+        }
+    }
+
+    fn supports_instancing(&self) -> bool {
+        true
+    }
+
+    fn clear(&self, color: [f32; 4], depth: bool) {
+        let rgba = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        for pixel in self.color.borrow_mut().chunks_exact_mut(4) {
+            pixel.copy_from_slice(&rgba);
+        }
+        if depth {
+            self.depth.borrow_mut().fill(1.0);
+        }
+    }
+
+    fn set_viewport(&self, width: u32, height: u32) {
+        let (width, height) = (width as usize, height as usize);
+        self.viewport.set(Viewport { width, height });
+        *self.color.borrow_mut() = vec![0; width * height * 4];
+        *self.depth.borrow_mut() = vec![1.0; width * height];
+    }
+
+    fn set_depth_test(&self, enabled: bool) {
+        self.depth_test.set(enabled);
+    }
+
+    fn set_depth_write(&self, enabled: bool) {
+        self.depth_write.set(enabled);
+    }
+
+    fn set_blending(&self, enabled: bool) {
+        self.blending.set(enabled);
+    }
+
+    fn read_pixels(&self) -> Vec<u8> {
+        self.color.borrow().clone()
+    }
+
+    fn name(&self) -> &'static str {
+        "software"
+    }
+}
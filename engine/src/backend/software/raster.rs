@@ -0,0 +1,265 @@
+//! Clipping and scan conversion. Primitives are clipped against the near and far planes
+//! in clip space, then walked pixel by pixel; every covered pixel centre becomes a call
+//! to the fragment callback with perspective-correct varyings.
+
+use crate::math::{Vec2, Vec4};
+use super::shaders::{Varyings, VertexOut, MAX_VARYINGS};
+
+/// GL implementations commonly cap point sprites around this size
+const MAX_POINT_SIZE: f32 = 64.0;
+
+/// One fragment: pixel column and row (top row first), window depth in 0..1, the
+/// interpolated varyings, and `gl_PointCoord` for points
+pub type Fragment<'a> = dyn FnMut(usize, usize, f32, &Varyings, Vec2) + 'a;
+
+/// Framebuffer size in pixels
+#[derive(Clone, Copy, Debug)]
+pub struct Viewport {
+    pub width: usize,
+    pub height: usize,
+}
+
+/// A vertex after the perspective divide
+#[derive(Clone, Copy)]
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    z: f32,                // Window depth, 0 at the near plane
+    inv_w: f32,            // For perspective-correct interpolation
+    varyings: Varyings,    // Already divided by w
+}
+
+impl Viewport {
+    fn project(&self, vertex: &VertexOut) -> ScreenVertex {
+        let inv_w = 1.0 / vertex.position.w;
+        let ndc = vertex.position.truncate() * inv_w;
+        ScreenVertex {
+            x: (ndc.x * 0.5 + 0.5) * self.width as f32,
+            y: (0.5 - ndc.y * 0.5) * self.height as f32,
+            z: ndc.z * 0.5 + 0.5,
+            inv_w,
+            varyings: vertex.varyings.map(|v| v * inv_w),
+        }
+    }
+}
+
+fn lerp_vertex(a: &VertexOut, b: &VertexOut, t: f32) -> VertexOut {
+    let mut varyings = [0.0; MAX_VARYINGS];
+    for (i, varying) in varyings.iter_mut().enumerate() {
+        *varying = a.varyings[i] + (b.varyings[i] - a.varyings[i]) * t;
+    }
+    VertexOut {
+        position: a.position + (b.position - a.position) * t,
+        point_size: a.point_size + (b.point_size - a.point_size) * t,
+        varyings,
+    }
+}
+
+/// Signed distances to the near and far planes; inside when both are non-negative
+fn plane_distances(position: Vec4) -> [f32; 2] {
+    [position.z + position.w, position.w - position.z]
+}
+
+/// Sutherland-Hodgman against the near and far planes
+fn clip_polygon(polygon: Vec<VertexOut>) -> Vec<VertexOut> {
+    let mut polygon = polygon;
+    for plane in 0..2 {
+        let mut clipped = Vec::with_capacity(polygon.len() + 2);
+        for (i, current) in polygon.iter().enumerate() {
+            let next = &polygon[(i + 1) % polygon.len()];
+            let d_current = plane_distances(current.position)[plane];
+            let d_next = plane_distances(next.position)[plane];
+
+            if d_current >= 0.0 {
+                clipped.push(*current);
+            }
+            if (d_current >= 0.0) != (d_next >= 0.0) {
+                clipped.push(lerp_vertex(current, next, d_current / (d_current - d_next)));
+            }
+        }
+        polygon = clipped;
+        if polygon.is_empty() {
+            break;
+        }
+    }
+    polygon
+}
+
+/// Sub-pixel steps per pixel that triangle vertices snap to, as on GPUs, so edge tests
+/// are exact and two triangles sharing an edge agree on which side every pixel is
+const SUBPIXEL_STEPS: f32 = 256.0;
+
+/// A point on the sub-pixel grid; wide enough that edge products never overflow
+type Fixed = [i128; 2];
+
+fn snap(x: f32, y: f32) -> Fixed {
+    [(x * SUBPIXEL_STEPS).round() as i64 as i128, (y * SUBPIXEL_STEPS).round() as i64 as i128]
+}
+
+/// Twice the signed area of (a, b, p); positive when p is on the interior side of a
+/// positively wound edge from a to b
+fn edge(a: Fixed, b: Fixed, p: Fixed) -> i128 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// Top-left fill rule: pixel centres exactly on an edge belong to the triangle only on its
+/// top and left edges, so pixels on an edge shared by two triangles are shaded once. With
+/// rows running downwards and positive winding, those are the edges going up the screen
+/// and the horizontal edges going right.
+fn owns_edge(a: Fixed, b: Fixed) -> bool {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    dy < 0 || (dy == 0 && dx > 0)
+}
+
+/// Fill a triangle, both windings, sampling at pixel centres with the top-left fill rule
+pub fn triangle(viewport: Viewport, vertices: [&VertexOut; 3], fragment: &mut Fragment) {
+    let polygon = clip_polygon(vertices.iter().map(|v| **v).collect());
+    if polygon.len() < 3 {
+        return;
+    }
+
+    let screen: Vec<ScreenVertex> = polygon.iter().map(|v| viewport.project(v)).collect();
+    for i in 1..screen.len() - 1 {
+        fill(viewport, [&screen[0], &screen[i], &screen[i + 1]], fragment);
+    }
+}
+
+fn fill(viewport: Viewport, [a, b, c]: [&ScreenVertex; 3], fragment: &mut Fragment) {
+    if [a, b, c].iter().any(|v| !(v.x.is_finite() && v.y.is_finite())) {
+        return;
+    }
+    let (mut b, mut c) = (b, c);
+    let mut area = edge(snap(a.x, a.y), snap(b.x, b.y), snap(c.x, c.y));
+    if area == 0 {
+        return;
+    }
+    // Wind every triangle the same way so the fill rule picks the same edges for both windings
+    if area < 0 {
+        std::mem::swap(&mut b, &mut c);
+        area = -area;
+    }
+    let [fa, fb, fc] = [snap(a.x, a.y), snap(b.x, b.y), snap(c.x, c.y)];
+    let owned = [owns_edge(fb, fc), owns_edge(fc, fa), owns_edge(fa, fb)];
+
+    let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as usize;
+    let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as usize;
+    let max_x = (a.x.max(b.x).max(c.x).ceil().max(0.0) as usize).min(viewport.width);
+    let max_y = (a.y.max(b.y).max(c.y).ceil().max(0.0) as usize).min(viewport.height);
+
+    let mut varyings = [0.0; MAX_VARYINGS];
+    for y in min_y..max_y {
+        let py = y as f32 + 0.5;
+        for x in min_x..max_x {
+            let p = snap(x as f32 + 0.5, py);
+            let edges = [edge(fb, fc, p), edge(fc, fa, p), edge(fa, fb, p)];
+            if edges.iter().zip(owned).any(|(e, owned)| *e < 0 || (*e == 0 && !owned)) {
+                continue;
+            }
+
+            let weights = edges.map(|e| (e as f64 / area as f64) as f32);
+            let depth = weights[0] * a.z + weights[1] * b.z + weights[2] * c.z;
+            let inv_w = weights[0] * a.inv_w + weights[1] * b.inv_w + weights[2] * c.inv_w;
+            for (i, varying) in varyings.iter_mut().enumerate() {
+                *varying = (weights[0] * a.varyings[i] + weights[1] * b.varyings[i] + weights[2] * c.varyings[i]) / inv_w;
+            }
+            fragment(x, y, depth, &varyings, Vec2::ZERO);
+        }
+    }
+}
+
+/// One-pixel-wide line that leaves out its last pixel, so strips do not blend their joints twice
+pub fn line(viewport: Viewport, start: &VertexOut, end: &VertexOut, fragment: &mut Fragment) {
+    // Clip the segment parametrically against the near and far planes
+    let (mut t0, mut t1) = (0.0_f32, 1.0_f32);
+    for plane in 0..2 {
+        let d0 = plane_distances(start.position)[plane];
+        let d1 = plane_distances(end.position)[plane];
+        if d0 < 0.0 && d1 < 0.0 {
+            return;
+        }
+        if d0 < 0.0 {
+            t0 = t0.max(d0 / (d0 - d1));
+        } else if d1 < 0.0 {
+            t1 = t1.min(d0 / (d0 - d1));
+        }
+    }
+    if t0 >= t1 {
+        return;
+    }
+
+    let a = viewport.project(&lerp_vertex(start, end, t0));
+    let b = viewport.project(&lerp_vertex(start, end, t1));
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let steps = dx.abs().max(dy.abs()).round();
+    if !steps.is_finite() || steps < 1.0 {
+        return;
+    }
+
+    let mut varyings = [0.0; MAX_VARYINGS];
+    for step in 0..steps as usize {
+        let t = (step as f32 + 0.5) / steps;
+        let (x, y) = (a.x + dx * t, a.y + dy * t);
+        if x < 0.0 || y < 0.0 || x >= viewport.width as f32 || y >= viewport.height as f32 {
+            continue;
+        }
+
+        let depth = a.z + (b.z - a.z) * t;
+        let inv_w = a.inv_w + (b.inv_w - a.inv_w) * t;
+        for (i, varying) in varyings.iter_mut().enumerate() {
+            *varying = (a.varyings[i] + (b.varyings[i] - a.varyings[i]) * t) / inv_w;
+        }
+        fragment(x as usize, y as usize, depth, &varyings, Vec2::ZERO);
+    }
+}
+
+/// Square point sprite of `point_size` pixels; dropped entirely when its centre is clipped
+pub fn point(viewport: Viewport, vertex: &VertexOut, fragment: &mut Fragment) {
+    if plane_distances(vertex.position).iter().any(|d| *d < 0.0) || vertex.position.w <= 0.0 {
+        return;
+    }
+
+    let center = viewport.project(vertex);
+    let size = vertex.point_size.clamp(1.0, MAX_POINT_SIZE);
+    let (left, top) = (center.x - size * 0.5, center.y - size * 0.5);
+
+    // Pixels whose centres fall inside the square
+    let first = |edge: f32| (edge - 0.5).ceil().max(0.0) as usize;
+    let last = |edge: f32, limit: usize| ((edge + size - 0.5).ceil().max(0.0) as usize).min(limit);
+
+    for y in first(top)..last(top, viewport.height) {
+        for x in first(left)..last(left, viewport.width) {
+            let point_coord = Vec2::new((x as f32 + 0.5 - left) / size, (y as f32 + 0.5 - top) / size);
+            fragment(x, y, center.z, &vertex.varyings, point_coord);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(x: f32, y: f32) -> VertexOut {
+        VertexOut { position: Vec4::new(x, y, 0.0, 1.0), point_size: 1.0, varyings: [0.0; MAX_VARYINGS] }
+    }
+
+    #[test]
+    fn shared_edges_are_shaded_once() {
+        // Four triangles meeting in the middle of an 8x8 target; both diagonals run through
+        // pixel centres, and the windings alternate
+        let viewport = Viewport { width: 8, height: 8 };
+        let [top_left, top_right, bottom_right, bottom_left, center] =
+            [vertex(-1.0, 1.0), vertex(1.0, 1.0), vertex(1.0, -1.0), vertex(-1.0, -1.0), vertex(0.0, 0.0)];
+        let triangles = [
+            [&top_left, &top_right, &center],
+            [&bottom_right, &top_right, &center],
+            [&bottom_right, &bottom_left, &center],
+            [&top_left, &bottom_left, &center],
+        ];
+
+        let mut coverage = [0; 64];
+        for vertices in triangles {
+            triangle(viewport, vertices, &mut |x, y, _, _, _| coverage[y * 8 + x] += 1);
+        }
+        assert_eq!(coverage, [1; 64]);
+    }
+}
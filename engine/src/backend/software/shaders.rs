@@ -0,0 +1,466 @@
+//! Rust versions of every `ShaderKind`, following the GLSL in `crate::shaders` line by
+//! line so software renders match the browser's.
+
+use std::collections::HashMap;
+use std::f32::consts::FRAC_1_PI;
+use crate::backend::Uniform;
+use crate::math::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use crate::shaders::ShaderKind;
+
+/// Enough interpolated floats for the largest shader, `Lit`
+pub const MAX_VARYINGS: usize = 8;
+pub type Varyings = [f32; MAX_VARYINGS];
+
+/// Enough attributes for the largest shader, `Orbit`
+pub const MAX_ATTRIBUTES: usize = 5;
+
+/// Everything the vertex stage hands to the rasterizer
+#[derive(Clone, Copy, Debug)]
+pub struct VertexOut {
+    pub position: Vec4,  // Clip space
+    pub point_size: f32, // Pixels; only used for points
+    pub varyings: Varyings,
+}
+
+/// A texture in system memory, rows top to bottom
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Texture {
+    /// Bilinear lookup with the same addressing as the WebGL backend: `v = 1` is the top
+    /// row, power-of-two textures repeat in `u` and everything else clamps
+    pub fn sample(&self, uv: Vec2) -> Vec4 {
+        let (width, height) = (self.width as i64, self.height as i64);
+        if width == 0 || height == 0 {
+            return Vec4::new(0.0, 0.0, 0.0, 1.0);
+        }
+        let repeat = self.width.is_power_of_two() && self.height.is_power_of_two();
+
+        let x = uv.x * self.width as f32 - 0.5;
+        let y = (1.0 - uv.y) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let texel = |x: i64, y: i64| {
+            let x = if repeat { x.rem_euclid(width) } else { x.clamp(0, width - 1) };
+            let y = y.clamp(0, height - 1);
+            let i = ((y * width + x) * 4) as usize;
+            let [r, g, b, a] = [0, 1, 2, 3].map(|c| self.pixels[i + c] as f32 / 255.0);
+            Vec4::new(r, g, b, a)
+        };
+
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = texel(x0, y0) * (1.0 - fx) + texel(x0 + 1, y0) * fx;
+        let bottom = texel(x0, y0 + 1) * (1.0 - fx) + texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+/// Uniform values as the GLSL would see them; anything unset reads as zero
+struct Uniforms<'a>(&'a HashMap<String, Uniform>);
+
+impl Uniforms<'_> {
+    fn float(&self, name: &str) -> f32 {
+        match self.0.get(name) {
+            Some(Uniform::Float(value)) => *value,
+            _ => 0.0,
+        }
+    }
+
+    fn bool(&self, name: &str) -> bool {
+        match self.0.get(name) {
+            Some(Uniform::Bool(value)) => *value,
+            Some(Uniform::Int(value)) => *value != 0,
+            _ => false,
+        }
+    }
+
+    fn vec3(&self, name: &str) -> Vec3 {
+        match self.0.get(name) {
+            Some(Uniform::Vec3(value)) => Vec3::from(*value),
+            _ => Vec3::ZERO,
+        }
+    }
+
+    fn vec4(&self, name: &str) -> Vec4 {
+        match self.0.get(name) {
+            Some(Uniform::Vec4(value)) => Vec4::from(*value),
+            _ => Vec4::ZERO,
+        }
+    }
+
+    fn mat3(&self, name: &str) -> Mat3 {
+        match self.0.get(name) {
+            Some(Uniform::Mat3(m)) => Mat3::from_cols(
+                Vec3::new(m[0], m[1], m[2]),
+                Vec3::new(m[3], m[4], m[5]),
+                Vec3::new(m[6], m[7], m[8]),
+            ),
+            _ => Mat3::from_cols(Vec3::ZERO, Vec3::ZERO, Vec3::ZERO),
+        }
+    }
+
+    fn mat4(&self, name: &str) -> Mat4 {
+        match self.0.get(name) {
+            Some(Uniform::Mat4(m)) => Mat4::from_cols_array(m),
+            _ => Mat4::from_cols(Vec4::ZERO, Vec4::ZERO, Vec4::ZERO, Vec4::ZERO),
+        }
+    }
+
+    fn texture<'t>(&self, name: &str, textures: &'t [Option<Texture>]) -> Option<&'t Texture> {
+        match self.0.get(name) {
+            Some(Uniform::Texture(handle)) => textures.get(handle.0).and_then(Option::as_ref),
+            _ => None,
+        }
+    }
+}
+
+/// A program with its uniforms resolved for one draw
+pub enum Shader<'a> {
+    Basic {
+        matrix: Mat4,
+        color: Vec3,
+    },
+    Lit {
+        model: Mat4,
+        view_projection: Mat4,
+        normal_matrix: Mat3,
+        color: Vec3,
+        emissive: bool,
+        diffuse_map: Option<Option<&'a Texture>>, // Outer None when the map is off; inner None when it is missing
+        light_position: Vec3,
+        light: Vec3, // Color times intensity
+        ambient: f32,
+        specular: f32,
+        shininess: f32,
+        camera_position: Vec3,
+    },
+    Line {
+        model_view_projection: Mat4,
+        color: Vec4,
+    },
+    Orbit {
+        view_projection: Mat4,
+    },
+    Ring {
+        model: Mat4,
+        view_projection: Mat4,
+        color: Vec3,
+        opacity: f32,
+        light_position: Vec3,
+        light: Vec3,
+        ambient: f32,
+        planet: Option<(Vec3, f32)>, // Center and radius, when the shadow is on
+    },
+    Trail {
+        view_projection: Mat4,
+        color: Vec4,
+    },
+    Sky {
+        view_projection: Mat4,
+        to_galactic: Mat3,
+        intensity: f32,
+        nebulae: bool,
+    },
+    Starfield {
+        view_projection: Mat4,
+    },
+}
+
+impl<'a> Shader<'a> {
+    pub fn bind(kind: ShaderKind, uniforms: &HashMap<String, Uniform>, textures: &'a [Option<Texture>]) -> Self {
+        let uniforms = Uniforms(uniforms);
+        let view_projection = || uniforms.mat4("u_projection_matrix") * uniforms.mat4("u_view_matrix");
+
+        match kind {
+            ShaderKind::Basic => Shader::Basic {
+                matrix: uniforms.mat4("matrix"),
+                color: uniforms.vec3("uColor"),
+            },
+            ShaderKind::Lit => Shader::Lit {
+                model: uniforms.mat4("u_model_matrix"),
+                view_projection: view_projection(),
+                normal_matrix: uniforms.mat3("u_normal_matrix"),
+                color: uniforms.vec3("u_color"),
+                emissive: uniforms.bool("u_emissive"),
+                diffuse_map: uniforms
+                    .bool("u_has_diffuse_map")
+                    .then(|| uniforms.texture("u_diffuse_map", textures)),
+                light_position: uniforms.vec3("u_light_position"),
+                light: uniforms.vec3("u_light_color") * uniforms.float("u_light_intensity"),
+                ambient: uniforms.float("u_ambient"),
+                specular: uniforms.float("u_specular"),
+                shininess: uniforms.float("u_shininess"),
+                camera_position: uniforms.vec3("u_camera_position"),
+            },
+            ShaderKind::Line => Shader::Line {
+                model_view_projection: view_projection() * uniforms.mat4("u_model_matrix"),
+                color: uniforms.vec4("u_color"),
+            },
+            ShaderKind::Orbit => Shader::Orbit {
+                view_projection: view_projection(),
+            },
+            ShaderKind::Ring => Shader::Ring {
+                model: uniforms.mat4("u_model_matrix"),
+                view_projection: view_projection(),
+                color: uniforms.vec3("u_color"),
+                opacity: uniforms.float("u_opacity"),
+                light_position: uniforms.vec3("u_light_position"),
+                light: uniforms.vec3("u_light_color") * uniforms.float("u_light_intensity"),
+                ambient: uniforms.float("u_ambient"),
+                planet: uniforms
+                    .bool("u_shadow")
+                    .then(|| (uniforms.vec3("u_planet_center"), uniforms.float("u_planet_radius"))),
+            },
+            ShaderKind::Trail => Shader::Trail {
+                view_projection: view_projection(),
+                color: uniforms.vec4("u_color"),
+            },
+            ShaderKind::Sky => Shader::Sky {
+                view_projection: uniforms.mat4("u_projection_matrix") * uniforms.mat4("u_view_rotation"),
+                to_galactic: uniforms.mat3("u_to_galactic"),
+                intensity: uniforms.float("u_intensity"),
+                nebulae: uniforms.bool("u_nebulae"),
+            },
+            ShaderKind::Starfield => Shader::Starfield {
+                view_projection: view_projection(),
+            },
+        }
+    }
+
+    /// Attribute names in the order `vertex` receives them
+    pub fn attributes(&self) -> &'static [&'static str] {
+        match self {
+            Shader::Basic { .. } | Shader::Line { .. } | Shader::Sky { .. } => &["position"],
+            Shader::Lit { .. } => &["position", "normal", "uv"],
+            Shader::Orbit { .. } => &["position", "i_center", "i_major_axis", "i_minor_axis", "i_color"],
+            Shader::Ring { .. } => &["position", "uv"],
+            Shader::Trail { .. } => &["position", "freshness"],
+            Shader::Starfield { .. } => &["a_star_position", "a_brightness", "a_size", "a_color"],
+        }
+    }
+
+    /// Run the vertex stage. Attributes arrive as `vec4`s filled out with (0, 0, 0, 1), as in GL.
+    pub fn vertex(&self, attributes: &[Vec4; MAX_ATTRIBUTES]) -> VertexOut {
+        let mut varyings = [0.0; MAX_VARYINGS];
+        let position = attributes[0];
+        let point = position.truncate().extend(1.0);
+
+        let (clip, point_size) = match self {
+            Shader::Basic { matrix, .. } => {
+                let color = position.truncate() * 0.5 + Vec3::splat(0.5);
+                varyings[..3].copy_from_slice(&color.to_array());
+                (*matrix * position, 1.0)
+            }
+            Shader::Lit { model, view_projection, normal_matrix, .. } => {
+                let world = *model * point;
+                let normal = *normal_matrix * attributes[1].truncate();
+                varyings[..3].copy_from_slice(&world.truncate().to_array());
+                varyings[3..6].copy_from_slice(&normal.to_array());
+                varyings[6..8].copy_from_slice(&[attributes[2].x, attributes[2].y]);
+                (*view_projection * world, 1.0)
+            }
+            Shader::Line { model_view_projection, .. } => (*model_view_projection * point, 1.0),
+            Shader::Orbit { view_projection } => {
+                let world = attributes[1].truncate() + attributes[2].truncate() * position.x + attributes[3].truncate() * position.y;
+                varyings[..4].copy_from_slice(&attributes[4].to_array());
+                (*view_projection * world.extend(1.0), 1.0)
+            }
+            Shader::Ring { model, view_projection, .. } => {
+                let world = *model * point;
+                varyings[..3].copy_from_slice(&world.truncate().to_array());
+                varyings[3] = attributes[1].x;
+                (*view_projection * world, 1.0)
+            }
+            Shader::Trail { view_projection, .. } => {
+                varyings[0] = attributes[1].x;
+                (*view_projection * point, 1.0)
+            }
+            Shader::Sky { view_projection, .. } => {
+                varyings[..3].copy_from_slice(&position.truncate().to_array());
+                (*view_projection * point, 1.0)
+            }
+            Shader::Starfield { view_projection } => {
+                let clip = *view_projection * point;
+                let size = attributes[2].x * 250.0 / clip.truncate().length();
+                varyings[0] = attributes[1].x;
+                varyings[1..4].copy_from_slice(&attributes[3].truncate().to_array());
+                (clip, size)
+            }
+        };
+
+        VertexOut { position: clip, point_size, varyings }
+    }
+
+    /// Run the fragment stage; `point_coord` is only meaningful for points
+    pub fn fragment(&self, varyings: &Varyings, point_coord: Vec2) -> Vec4 {
+        let vec3 = |i: usize| Vec3::new(varyings[i], varyings[i + 1], varyings[i + 2]);
+
+        match self {
+            Shader::Basic { color, .. } => vec3(0).mul_elements(*color).extend(1.0),
+            Shader::Lit {
+                color,
+                emissive,
+                diffuse_map,
+                light_position,
+                light,
+                ambient,
+                specular,
+                shininess,
+                camera_position,
+                ..
+            } => {
+                let world_position = vec3(0);
+                let base_color = match diffuse_map {
+                    Some(Some(texture)) => texture.sample(Vec2::new(varyings[6], varyings[7])).truncate(),
+                    Some(None) => Vec3::ZERO, // Sampling an unbound texture gives black
+                    None => *color,
+                };
+                if *emissive {
+                    return base_color.extend(1.0);
+                }
+
+                let normal = vec3(3).normalize();
+                let to_light = (*light_position - world_position).normalize();
+                let to_camera = (*camera_position - world_position).normalize();
+                let half_vector = (to_light + to_camera).normalize();
+
+                let diffuse = normal.dot(to_light).max(0.0);
+                let highlight = if diffuse > 0.0 { normal.dot(half_vector).max(0.0).powf(*shininess) } else { 0.0 };
+
+                let lit = base_color.mul_elements(Vec3::splat(*ambient) + *light * diffuse) + *light * (specular * highlight);
+                lit.extend(1.0)
+            }
+            Shader::Line { color, .. } => *color,
+            Shader::Orbit { .. } => Vec4::new(varyings[0], varyings[1], varyings[2], varyings[3]),
+            Shader::Ring { color, opacity, light_position, light, ambient, planet, .. } => {
+                let world_position = vec3(0);
+                let radial = varyings[3];
+
+                let edges = smoothstep(0.0, 0.04, radial) * (1.0 - smoothstep(0.96, 1.0, radial));
+                let bands = 0.75 + 0.25 * (radial * 47.0).sin() * (radial * 13.0).sin();
+                let alpha = opacity * edges * bands;
+
+                let mut lit = 1.0;
+                if let Some((center, radius)) = planet {
+                    let to_light = (*light_position - world_position).normalize();
+                    let to_planet = *center - world_position;
+                    let along = to_planet.dot(to_light);
+                    let miss = (to_planet - to_light * along).length() / radius;
+                    if along > 0.0 {
+                        lit = smoothstep(0.95, 1.05, miss);
+                    }
+                }
+
+                color.mul_elements(Vec3::splat(*ambient) + *light * lit).extend(alpha)
+            }
+            Shader::Trail { color, .. } => {
+                let freshness = varyings[0];
+                Vec4::new(color.x, color.y, color.z, color.w * freshness * freshness)
+            }
+            Shader::Sky { to_galactic, intensity, nebulae, .. } => sky(vec3(0), to_galactic, *intensity, *nebulae),
+            Shader::Starfield { .. } => {
+                let brightness = varyings[0];
+                let star_color = vec3(1);
+
+                let dist = (point_coord - Vec2::new(0.5, 0.5)).length();
+                let mut intensity = 1.0 - smoothstep(0.0, 0.5, dist);
+                intensity = intensity.powf(if brightness > 0.8 { 0.4 } else { 0.8 });
+
+                let color = star_color.lerp(Vec3::ONE, 0.5 * intensity.powi(4) * brightness);
+                color.extend(intensity * (0.5 + 0.5 * brightness))
+            }
+        }
+    }
+}
+
+fn sky(direction: Vec3, to_galactic: &Mat3, intensity: f32, nebulae: bool) -> Vec4 {
+    let galactic = (*to_galactic * direction.normalize()).normalize();
+    let latitude = galactic.z.clamp(-1.0, 1.0).asin();
+    let toward_center = galactic.x * 0.5 + 0.5;
+
+    let width = mix(0.12, 0.25, toward_center * toward_center);
+    let disk = (-latitude * latitude / (width * width)).exp();
+    let bulge = glow_at(galactic, 0.0, 0.0, 0.35);
+    let clouds = fbm(galactic * 6.0);
+    let mut glow = (disk * (0.45 + 0.55 * toward_center) + bulge) * (0.55 + 0.9 * clouds);
+
+    let dust = smoothstep(0.45, 0.75, fbm(galactic * 9.0 + Vec3::splat(7.1))) * (-latitude * latitude / 0.004).exp();
+    glow *= 1.0 - 0.8 * dust;
+
+    let tint = Vec3::new(0.55, 0.6, 0.75).lerp(Vec3::new(0.95, 0.85, 0.7), bulge + 0.3 * toward_center);
+    let mut color = tint * glow;
+
+    if nebulae {
+        let detail = fbm(galactic * 14.0);
+        for (tint, l, b, radius) in [
+            (Vec3::new(0.9, 0.25, 0.3), -150.4_f32, -19.4_f32, 0.12), // Orion
+            (Vec3::new(0.85, 0.3, 0.4), 6.0, -1.2, 0.06),             // Lagoon
+            (Vec3::new(0.3, 0.5, 0.9), -193.3, -23.4, 0.05),          // Pleiades
+            (Vec3::new(0.8, 0.35, 0.35), -72.4, -0.6, 0.1),           // Carina
+            (Vec3::new(0.8, 0.3, 0.35), 85.0, -1.0, 0.12),            // North America
+        ] {
+            color += tint * (glow_at(galactic, l.to_radians(), b.to_radians(), radius) * detail);
+        }
+    }
+
+    color *= intensity;
+    let alpha = color.x.max(color.y).max(color.z).clamp(0.0, 1.0);
+    if alpha > 0.0 {
+        (color / alpha).extend(alpha)
+    } else {
+        Vec4::new(0.0, 0.0, 0.0, alpha)
+    }
+}
+
+fn hash(p: Vec3) -> f32 {
+    let scramble = |x: f32| fract(x * FRAC_1_PI + 0.1);
+    let p = Vec3::new(scramble(p.x), scramble(p.y), scramble(p.z)) * 17.0;
+    fract(p.x * p.y * p.z * (p.x + p.y + p.z))
+}
+
+fn value_noise(x: Vec3) -> f32 {
+    let i = Vec3::new(x.x.floor(), x.y.floor(), x.z.floor());
+    let f = x - i;
+    let f = Vec3::new(f.x * f.x * (3.0 - 2.0 * f.x), f.y * f.y * (3.0 - 2.0 * f.y), f.z * f.z * (3.0 - 2.0 * f.z));
+    let corner = |dx: f32, dy: f32, dz: f32| hash(i + Vec3::new(dx, dy, dz));
+
+    mix(
+        mix(mix(corner(0.0, 0.0, 0.0), corner(1.0, 0.0, 0.0), f.x), mix(corner(0.0, 1.0, 0.0), corner(1.0, 1.0, 0.0), f.x), f.y),
+        mix(mix(corner(0.0, 0.0, 1.0), corner(1.0, 0.0, 1.0), f.x), mix(corner(0.0, 1.0, 1.0), corner(1.0, 1.0, 1.0), f.x), f.y),
+        f.z,
+    )
+}
+
+fn fbm(mut p: Vec3) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    for _ in 0..5 {
+        sum += amplitude * value_noise(p);
+        p *= 2.03;
+        amplitude *= 0.5;
+    }
+    sum
+}
+
+fn glow_at(galactic: Vec3, l: f32, b: f32, radius: f32) -> f32 {
+    let center = Vec3::new(b.cos() * l.cos(), b.cos() * l.sin(), b.sin());
+    let angle = galactic.dot(center).clamp(-1.0, 1.0).acos();
+    (-angle * angle / (radius * radius)).exp()
+}
+
+fn fract(x: f32) -> f32 {
+    x - x.floor()
+}
+
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,349 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use wasm_bindgen::JsCast;
+use web_sys::{AngleInstancedArrays, WebGlBuffer, WebGlProgram, WebGlRenderingContext, WebGlTexture, WebGlUniformLocation};
+use crate::shaders::ShaderKind;
+use crate::shapes::geometry::Primitive;
+use crate::texture::TextureSource;
+use super::gl::gl_helpers;
+use super::{insert, BufferData, BufferHandle, BufferUsage, DrawCall, GraphicsBackend, ProgramHandle, TextureHandle, Uniform};
+
+/// A compiled program with its attribute and uniform locations looked up on first use
+struct Program {
+    program: WebGlProgram,
+    attributes: RefCell<HashMap<String, i32>>,
+    uniforms: RefCell<HashMap<String, Option<WebGlUniformLocation>>>,
+    texture_units: RefCell<HashMap<String, u32>>, // Sampler name to the unit it was given
+}
+
+/// `GraphicsBackend` on a WebGL 1 context. Instanced draws need `ANGLE_instanced_arrays`,
+/// which nearly every WebGL 1 implementation has; without it they are skipped, and
+/// `supports_instancing` says so.
+pub struct WebGlBackend {
+    context: WebGlRenderingContext,
+    instancing: Option<AngleInstancedArrays>,
+    buffers: RefCell<Vec<Option<(WebGlBuffer, u32)>>>, // Buffer and its bind target
+    programs: RefCell<Vec<Option<Program>>>,
+    textures: RefCell<Vec<Option<WebGlTexture>>>,
+    current_program: Cell<Option<ProgramHandle>>,
+    viewport: Cell<(u32, u32)>,
+}
+
+impl WebGlBackend {
+    pub fn new(context: WebGlRenderingContext) -> Self {
+        // Extension objects have no global constructor to check against, so skip the cast check
+        let instancing = context
+            .get_extension("ANGLE_instanced_arrays")
+            .ok()
+            .flatten()
+            .map(|extension| extension.unchecked_into::<AngleInstancedArrays>());
+
+        Self {
+            context,
+            instancing,
+            buffers: RefCell::new(Vec::new()),
+            programs: RefCell::new(Vec::new()),
+            textures: RefCell::new(Vec::new()),
+            current_program: Cell::new(None),
+            viewport: Cell::new((0, 0)),
+        }
+    }
+
+    fn use_program(&self, handle: ProgramHandle, program: &Program) {
+        if self.current_program.get() != Some(handle) {
+            self.context.use_program(Some(&program.program));
+            self.current_program.set(Some(handle));
+        }
+    }
+}
+
+gl_helpers!(WebGlRenderingContext);
+
+impl GraphicsBackend for WebGlBackend {
+    fn create_buffer(&self, data: BufferData, usage: BufferUsage) -> Result<BufferHandle, String> {
+        let buffer = self.context.create_buffer().ok_or("Failed to create buffer")?;
+        let target = buffer_target(&data);
+        self.context.bind_buffer(target, Some(&buffer));
+        upload_buffer(&self.context, target, data, usage);
+        Ok(BufferHandle(insert(&mut self.buffers.borrow_mut(), (buffer, target))))
+    }
+
+    fn update_buffer(&self, buffer: BufferHandle, data: BufferData) {
+        let buffers = self.buffers.borrow();
+        let Some((buffer, target)) = buffers.get(buffer.0).and_then(Option::as_ref) else { return };
+        self.context.bind_buffer(*target, Some(buffer));
+        upload_buffer(&self.context, *target, data, BufferUsage::Dynamic);
+    }
+
+    fn delete_buffer(&self, buffer: BufferHandle) {
+        if let Some((buffer, _)) = self.buffers.borrow_mut().get_mut(buffer.0).and_then(Option::take) {
+            self.context.delete_buffer(Some(&buffer));
+        }
+    }
+
+    fn create_program(&self, shader: ShaderKind) -> Result<ProgramHandle, String> {
+        let (vertex_source, fragment_source) = shader.glsl();
+        let program = create_program(&self.context, vertex_source, fragment_source)?;
+
+        let program = Program {
+            program,
+            attributes: RefCell::new(HashMap::new()),
+            uniforms: RefCell::new(HashMap::new()),
+            texture_units: RefCell::new(HashMap::new()),
+        };
+        Ok(ProgramHandle(insert(&mut self.programs.borrow_mut(), program)))
+    }
+
+    fn delete_program(&self, handle: ProgramHandle) {
+        if let Some(program) = self.programs.borrow_mut().get_mut(handle.0).and_then(Option::take) {
+            self.context.delete_program(Some(&program.program));
+        }
+        // The handle may be reused, so the next program on it must be bound afresh
+        if self.current_program.get() == Some(handle) {
+            self.current_program.set(None);
+        }
+    }
+
+    fn set_uniform(&self, handle: ProgramHandle, name: &str, value: Uniform) {
+        let programs = self.programs.borrow();
+        let Some(program) = programs.get(handle.0).and_then(Option::as_ref) else { return };
+        self.use_program(handle, program);
+
+        let mut uniforms = program.uniforms.borrow_mut();
+        let location = uniforms
+            .entry(name.to_string())
+            .or_insert_with(|| self.context.get_uniform_location(&program.program, name));
+        let Some(location) = location.as_ref() else { return };
+
+        let context = &self.context;
+        match value {
+            Uniform::Float(value) => context.uniform1f(Some(location), value),
+            Uniform::Int(value) => context.uniform1i(Some(location), value),
+            Uniform::Bool(value) => context.uniform1i(Some(location), value as i32),
+            Uniform::Vec3(value) => context.uniform3fv_with_f32_array(Some(location), &value),
+            Uniform::Vec4(value) => context.uniform4fv_with_f32_array(Some(location), &value),
+            Uniform::Mat3(value) => context.uniform_matrix3fv_with_f32_array(Some(location), false, &value),
+            Uniform::Mat4(value) => context.uniform_matrix4fv_with_f32_array(Some(location), false, &value),
+            Uniform::Texture(texture) => {
+                // Each sampler keeps the unit it was first given
+                let mut units = program.texture_units.borrow_mut();
+                let next_unit = units.len() as u32;
+                let unit = *units.entry(name.to_string()).or_insert(next_unit);
+
+                let textures = self.textures.borrow();
+                context.active_texture(WebGlRenderingContext::TEXTURE0 + unit);
+                context.bind_texture(WebGlRenderingContext::TEXTURE_2D, textures.get(texture.0).and_then(Option::as_ref));
+                context.uniform1i(Some(location), unit as i32);
+            }
+        }
+    }
+
+    fn create_texture(&self, source: &TextureSource) -> Result<TextureHandle, String> {
+        let context = &self.context;
+        let texture = context.create_texture().ok_or("Failed to create texture")?;
+        context.bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&texture));
+
+        // Flip so v = 1 is the top row, matching the UVs of the built-in shapes
+        context.pixel_storei(WebGlRenderingContext::UNPACK_FLIP_Y_WEBGL, 1);
+        let uploaded = upload_texture(context, source);
+        context.pixel_storei(WebGlRenderingContext::UNPACK_FLIP_Y_WEBGL, 0);
+        let (width, height) = match uploaded {
+            Ok(size) => size,
+            Err(error) => {
+                context.delete_texture(Some(&texture));
+                return Err(error);
+            }
+        };
+
+        // WebGL 1 only mipmaps and repeats power-of-two textures
+        let target = WebGlRenderingContext::TEXTURE_2D;
+        if width.is_power_of_two() && height.is_power_of_two() {
+            context.generate_mipmap(target);
+            context.tex_parameteri(target, WebGlRenderingContext::TEXTURE_MIN_FILTER, WebGlRenderingContext::LINEAR_MIPMAP_LINEAR as i32);
+            context.tex_parameteri(target, WebGlRenderingContext::TEXTURE_WRAP_S, WebGlRenderingContext::REPEAT as i32);
+        } else {
+            context.tex_parameteri(target, WebGlRenderingContext::TEXTURE_MIN_FILTER, WebGlRenderingContext::LINEAR as i32);
+            context.tex_parameteri(target, WebGlRenderingContext::TEXTURE_WRAP_S, WebGlRenderingContext::CLAMP_TO_EDGE as i32);
+        }
+        context.tex_parameteri(target, WebGlRenderingContext::TEXTURE_MAG_FILTER, WebGlRenderingContext::LINEAR as i32);
+        context.tex_parameteri(target, WebGlRenderingContext::TEXTURE_WRAP_T, WebGlRenderingContext::CLAMP_TO_EDGE as i32);
+
+        Ok(TextureHandle(insert(&mut self.textures.borrow_mut(), texture)))
+    }
+
+    fn delete_texture(&self, texture: TextureHandle) {
+        if let Some(texture) = self.textures.borrow_mut().get_mut(texture.0).and_then(Option::take) {
+            self.context.delete_texture(Some(&texture));
+        }
+    }
+
+    fn draw(&self, call: &DrawCall) {
+        let instanced = call.instances != 1 || call.attributes.iter().any(|attribute| attribute.divisor != 0);
+        if instanced && self.instancing.is_none() {
+            return;
+        }
+
+        let programs = self.programs.borrow();
+        let Some(program) = programs.get(call.program.0).and_then(Option::as_ref) else { return };
+        self.use_program(call.program, program);
+        let buffers = self.buffers.borrow();
+        let context = &self.context;
+
+        let mut enabled = Vec::with_capacity(call.attributes.len());
+        for attribute in call.attributes {
+            let location = *program
+                .attributes
+                .borrow_mut()
+                .entry(attribute.name.to_string())
+                .or_insert_with(|| context.get_attrib_location(&program.program, attribute.name));
+            let Some((buffer, _)) = buffers.get(attribute.buffer.0).and_then(Option::as_ref) else { continue };
+            if location < 0 {
+                continue; // Not used by this program
+            }
+
+            context.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(buffer));
+            context.vertex_attrib_pointer_with_i32(
+                location as u32,
+                attribute.size,
+                WebGlRenderingContext::FLOAT,
+                false,
+                attribute.stride,
+                attribute.offset,
+            );
+            context.enable_vertex_attrib_array(location as u32);
+            if let (Some(instancing), true) = (&self.instancing, instanced) {
+                instancing.vertex_attrib_divisor_angle(location as u32, attribute.divisor);
+            }
+            enabled.push(location as u32);
+        }
+
+        let mode = match call.primitive {
+            Primitive::Triangles => WebGlRenderingContext::TRIANGLES,
+            Primitive::Lines => WebGlRenderingContext::LINES,
+            Primitive::LineLoop => WebGlRenderingContext::LINE_LOOP,
+            Primitive::LineStrip => WebGlRenderingContext::LINE_STRIP,
+            Primitive::Points => WebGlRenderingContext::POINTS,
+        };
+        let (first, count, instances) = (call.first as i32, call.count as i32, call.instances as i32);
+        let indices = call.indices.and_then(|indices| buffers.get(indices.0).and_then(Option::as_ref));
+        if let Some((indices, _)) = indices {
+            context.bind_buffer(WebGlRenderingContext::ELEMENT_ARRAY_BUFFER, Some(indices));
+        }
+        match (indices.is_some(), self.instancing.as_ref().filter(|_| instanced)) {
+            (true, Some(instancing)) => instancing.draw_elements_instanced_angle_with_i32(
+                mode,
+                count,
+                WebGlRenderingContext::UNSIGNED_SHORT,
+                first * 2,
+                instances,
+            ),
+            (true, None) => context.draw_elements_with_i32(mode, count, WebGlRenderingContext::UNSIGNED_SHORT, first * 2),
+            (false, Some(instancing)) => instancing.draw_arrays_instanced_angle(mode, first, count, instances),
+            (false, None) => context.draw_arrays(mode, first, count),
+        }
+
+        // Leave no arrays enabled, or divisors set, for the next program to trip over
+        for location in enabled {
+            if let (Some(instancing), true) = (&self.instancing, instanced) {
+                instancing.vertex_attrib_divisor_angle(location, 0);
+            }
+            context.disable_vertex_attrib_array(location);
+        }
+    }
+
+    fn supports_instancing(&self) -> bool {
+        self.instancing.is_some()
+    }
+
+    fn clear(&self, color: [f32; 4], depth: bool) {
+        self.context.clear_color(color[0], color[1], color[2], color[3]);
+        if depth {
+            self.context.depth_mask(true);
+            self.context.clear(WebGlRenderingContext::COLOR_BUFFER_BIT | WebGlRenderingContext::DEPTH_BUFFER_BIT);
+        } else {
+            self.context.clear(WebGlRenderingContext::COLOR_BUFFER_BIT);
+        }
+    }
+
+    fn set_viewport(&self, width: u32, height: u32) {
+        self.context.viewport(0, 0, width as i32, height as i32);
+        self.viewport.set((width, height));
+    }
+
+    fn set_depth_test(&self, enabled: bool) {
+        if enabled {
+            self.context.enable(WebGlRenderingContext::DEPTH_TEST);
+            self.context.depth_func(WebGlRenderingContext::LESS);
+        } else {
+            self.context.disable(WebGlRenderingContext::DEPTH_TEST);
+        }
+    }
+
+    fn set_depth_write(&self, enabled: bool) {
+        self.context.depth_mask(enabled);
+    }
+
+    fn set_blending(&self, enabled: bool) {
+        if enabled {
+            self.context.enable(WebGlRenderingContext::BLEND);
+            self.context.blend_func(WebGlRenderingContext::SRC_ALPHA, WebGlRenderingContext::ONE_MINUS_SRC_ALPHA);
+        } else {
+            self.context.disable(WebGlRenderingContext::BLEND);
+        }
+    }
+
+    /// Only meaningful before the browser composites the frame, since the drawing buffer
+    /// is not preserved
+    fn read_pixels(&self) -> Vec<u8> {
+        let (width, height) = self.viewport.get();
+        let row = width as usize * 4;
+        let mut pixels = vec![0; row * height as usize];
+        let _ = self.context.read_pixels_with_opt_u8_array(
+            0,
+            0,
+            width as i32,
+            height as i32,
+            WebGlRenderingContext::RGBA,
+            WebGlRenderingContext::UNSIGNED_BYTE,
+            Some(&mut pixels),
+        );
+
+        // GL rows run bottom to top
+        pixels.chunks_exact(row.max(1)).rev().flatten().copied().collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "webgl"
+    }
+}
+
+/// Upload `source` to the bound texture and return its size
+fn upload_texture(context: &WebGlRenderingContext, source: &TextureSource) -> Result<(u32, u32), String> {
+    let target = WebGlRenderingContext::TEXTURE_2D;
+    let rgba = WebGlRenderingContext::RGBA;
+    let bytes = WebGlRenderingContext::UNSIGNED_BYTE;
+
+    match source {
+        TextureSource::Rgba { width, height, pixels } => {
+            source.validate()?;
+            context
+                .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                    target, 0, rgba as i32, *width as i32, *height as i32, 0, rgba, bytes, Some(pixels),
+                )
+                .map_err(|_| "Failed to upload texture pixels")?;
+            Ok((*width, *height))
+        }
+        TextureSource::ImageData(data) => {
+            context
+                .tex_image_2d_with_u32_and_u32_and_image_data(target, 0, rgba as i32, rgba, bytes, data)
+                .map_err(|_| "Failed to upload image data")?;
+            Ok((data.width(), data.height()))
+        }
+        TextureSource::Image(image) => {
+            context
+                .tex_image_2d_with_u32_and_u32_and_image(target, 0, rgba as i32, rgba, bytes, image)
+                .map_err(|_| "Failed to upload image")?;
+            Ok((image.natural_width(), image.natural_height()))
+        }
+    }
+}
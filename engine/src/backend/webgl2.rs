@@ -0,0 +1,447 @@
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlTexture, WebGlUniformLocation, WebGlVertexArrayObject};
+use crate::shaders::{ShaderKind, UniformBlock, UNIFORM_BLOCKS};
+use crate::shapes::geometry::Primitive;
+use crate::texture::TextureSource;
+use super::gl::gl_helpers;
+use super::{insert, BufferData, BufferHandle, BufferUsage, DrawCall, GraphicsBackend, ProgramHandle, TextureHandle, Uniform};
+
+type Gl = WebGl2RenderingContext;
+
+/// A compiled program with its attribute and uniform locations looked up on first use
+struct Program {
+    program: WebGlProgram,
+    attributes: RefCell<HashMap<String, i32>>,
+    uniforms: RefCell<HashMap<String, Option<WebGlUniformLocation>>>,
+    texture_units: RefCell<HashMap<String, u32>>, // Sampler name to the unit it was given
+    blocks: Vec<usize>,                           // Indices into `UNIFORM_BLOCKS` of the blocks it declares
+}
+
+/// Everything a vertex array's setup depends on, so one is built per distinct draw layout
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct VertexArrayKey {
+    program: ProgramHandle,
+    indices: Option<BufferHandle>,
+    attributes: Vec<(String, BufferHandle, i32, i32, i32, u32)>, // Name, buffer, size, stride, offset, divisor
+}
+
+impl VertexArrayKey {
+    fn uses(&self, buffer: BufferHandle) -> bool {
+        self.indices == Some(buffer) || self.attributes.iter().any(|attribute| attribute.1 == buffer)
+    }
+}
+
+/// A uniform block's contents on the CPU, uploaded before the next draw after a change
+struct BlockBuffer {
+    block: &'static UniformBlock,
+    buffer: WebGlBuffer,
+    data: RefCell<Vec<f32>>,
+    dirty: Cell<bool>,
+}
+
+/// `GraphicsBackend` on a WebGL 2 context, running the GLSL ES 3.00 variants of each shader.
+///
+/// Each distinct set of attribute bindings is captured once in a vertex array object and
+/// rebound with a single call. Camera and light uniforms live in the uniform buffers of
+/// `shaders::UNIFORM_BLOCKS`, shared by every program that declares the block, so setting
+/// one on any of those programs sets it for all of them.
+pub struct WebGl2Backend {
+    context: Gl,
+    buffers: RefCell<Vec<Option<(WebGlBuffer, u32)>>>, // Buffer and its bind target
+    programs: RefCell<Vec<Option<Program>>>,
+    textures: RefCell<Vec<Option<WebGlTexture>>>,
+    vertex_arrays: RefCell<HashMap<VertexArrayKey, WebGlVertexArrayObject>>,
+    blocks: Vec<BlockBuffer>,
+    current_program: Cell<Option<ProgramHandle>>,
+    viewport: Cell<(u32, u32)>,
+}
+
+impl WebGl2Backend {
+    pub fn new(context: Gl) -> Result<Self, String> {
+        let blocks = UNIFORM_BLOCKS
+            .iter()
+            .map(|block| {
+                let buffer = context.create_buffer().ok_or("Failed to create uniform buffer")?;
+                context.bind_buffer(Gl::UNIFORM_BUFFER, Some(&buffer));
+                context.buffer_data_with_i32(Gl::UNIFORM_BUFFER, (block.size * 4) as i32, Gl::DYNAMIC_DRAW);
+                context.bind_buffer_base(Gl::UNIFORM_BUFFER, block.binding, Some(&buffer));
+                Ok(BlockBuffer { block, buffer, data: RefCell::new(vec![0.0; block.size]), dirty: Cell::new(false) })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self {
+            context,
+            buffers: RefCell::new(Vec::new()),
+            programs: RefCell::new(Vec::new()),
+            textures: RefCell::new(Vec::new()),
+            vertex_arrays: RefCell::new(HashMap::new()),
+            blocks,
+            current_program: Cell::new(None),
+            viewport: Cell::new((0, 0)),
+        })
+    }
+
+    fn use_program(&self, handle: ProgramHandle, program: &Program) {
+        if self.current_program.get() != Some(handle) {
+            self.context.use_program(Some(&program.program));
+            self.current_program.set(Some(handle));
+        }
+    }
+
+    /// Write `name` into one of `program`'s uniform blocks. False, leaving it to be set as
+    /// a plain uniform, when none of the program's blocks has a member of that name and type.
+    fn set_block_member(&self, program: &Program, name: &str, value: Uniform) -> bool {
+        let member = program.blocks.iter().find_map(|&index| {
+            let block = &self.blocks[index];
+            let &(glsl_type, _, offset) = block.block.members.iter().find(|(_, member, _)| *member == name)?;
+            Some((block, glsl_type, offset))
+        });
+        let Some((block, glsl_type, offset)) = member else { return false };
+
+        let floats: Vec<f32> = match (glsl_type, value) {
+            ("float", Uniform::Float(value)) => vec![value],
+            ("vec3", Uniform::Vec3(value)) => value.to_vec(),
+            ("vec4", Uniform::Vec4(value)) => value.to_vec(),
+            // std140 pads each matrix column to a vec4
+            ("mat3", Uniform::Mat3(value)) => value.chunks_exact(3).flat_map(|column| [column[0], column[1], column[2], 0.0]).collect(),
+            ("mat4", Uniform::Mat4(value)) => value.to_vec(),
+            _ => return false,
+        };
+        // A member running past its block is a mistake in `UNIFORM_BLOCKS`, not bad input
+        let mut data = block.data.borrow_mut();
+        let target = data
+            .get_mut(offset..offset + floats.len())
+            .unwrap_or_else(|| panic!("{} does not fit in the {} uniform block", name, block.block.name));
+        target.copy_from_slice(&floats);
+        block.dirty.set(true);
+        true
+    }
+
+    fn upload_blocks(&self) {
+        for block in self.blocks.iter().filter(|block| block.dirty.get()) {
+            self.context.bind_buffer(Gl::UNIFORM_BUFFER, Some(&block.buffer));
+            unsafe {
+                let array = js_sys::Float32Array::view(&block.data.borrow());
+                self.context.buffer_sub_data_with_i32_and_array_buffer_view(Gl::UNIFORM_BUFFER, 0, &array);
+            }
+            block.dirty.set(false);
+        }
+    }
+
+    /// Record `call`'s attribute bindings and index buffer in a new vertex array, left bound
+    fn create_vertex_array(&self, program: &Program, call: &DrawCall) -> Option<WebGlVertexArrayObject> {
+        let buffers = self.buffers.borrow();
+        let context = &self.context;
+        let vertex_array = context.create_vertex_array()?;
+        context.bind_vertex_array(Some(&vertex_array));
+
+        for attribute in call.attributes {
+            let location = *program
+                .attributes
+                .borrow_mut()
+                .entry(attribute.name.to_string())
+                .or_insert_with(|| context.get_attrib_location(&program.program, attribute.name));
+            let Some((buffer, _)) = buffers.get(attribute.buffer.0).and_then(Option::as_ref) else { continue };
+            if location < 0 {
+                continue; // Not used by this program
+            }
+
+            context.bind_buffer(Gl::ARRAY_BUFFER, Some(buffer));
+            context.vertex_attrib_pointer_with_i32(location as u32, attribute.size, Gl::FLOAT, false, attribute.stride, attribute.offset);
+            context.vertex_attrib_divisor(location as u32, attribute.divisor);
+            context.enable_vertex_attrib_array(location as u32);
+        }
+
+        if let Some(indices) = call.indices {
+            let Some((indices, _)) = buffers.get(indices.0).and_then(Option::as_ref) else {
+                context.bind_vertex_array(None);
+                context.delete_vertex_array(Some(&vertex_array));
+                return None;
+            };
+            context.bind_buffer(Gl::ELEMENT_ARRAY_BUFFER, Some(indices));
+        }
+        Some(vertex_array)
+    }
+}
+
+gl_helpers!(Gl);
+
+impl GraphicsBackend for WebGl2Backend {
+    fn create_buffer(&self, data: BufferData, usage: BufferUsage) -> Result<BufferHandle, String> {
+        let buffer = self.context.create_buffer().ok_or("Failed to create buffer")?;
+        let target = buffer_target(&data);
+        self.context.bind_buffer(target, Some(&buffer));
+        upload_buffer(&self.context, target, data, usage);
+        Ok(BufferHandle(insert(&mut self.buffers.borrow_mut(), (buffer, target))))
+    }
+
+    fn update_buffer(&self, buffer: BufferHandle, data: BufferData) {
+        let buffers = self.buffers.borrow();
+        let Some((buffer, target)) = buffers.get(buffer.0).and_then(Option::as_ref) else { return };
+        self.context.bind_buffer(*target, Some(buffer));
+        upload_buffer(&self.context, *target, data, BufferUsage::Dynamic);
+    }
+
+    /// Also drops every vertex array reading from the buffer, since its handle may be reused
+    fn delete_buffer(&self, buffer: BufferHandle) {
+        self.vertex_arrays.borrow_mut().retain(|key, vertex_array| {
+            let keep = !key.uses(buffer);
+            if !keep {
+                self.context.delete_vertex_array(Some(vertex_array));
+            }
+            keep
+        });
+        if let Some((buffer, _)) = self.buffers.borrow_mut().get_mut(buffer.0).and_then(Option::take) {
+            self.context.delete_buffer(Some(&buffer));
+        }
+    }
+
+    fn create_program(&self, shader: ShaderKind) -> Result<ProgramHandle, String> {
+        let (vertex_source, fragment_source) = shader.glsl_300();
+        let program = create_program(&self.context, &vertex_source, &fragment_source)?;
+
+        // Point whichever shared blocks the program declares at their buffers
+        let mut blocks = Vec::new();
+        for (block_index, block) in UNIFORM_BLOCKS.iter().enumerate() {
+            let index = self.context.get_uniform_block_index(&program, block.name);
+            if index != Gl::INVALID_INDEX {
+                self.context.uniform_block_binding(&program, index, block.binding);
+                blocks.push(block_index);
+            }
+        }
+
+        let program = Program {
+            program,
+            attributes: RefCell::new(HashMap::new()),
+            uniforms: RefCell::new(HashMap::new()),
+            texture_units: RefCell::new(HashMap::new()),
+            blocks,
+        };
+        Ok(ProgramHandle(insert(&mut self.programs.borrow_mut(), program)))
+    }
+
+    /// Also drops the program's vertex arrays, since its handle may be reused
+    fn delete_program(&self, handle: ProgramHandle) {
+        self.vertex_arrays.borrow_mut().retain(|key, vertex_array| {
+            let keep = key.program != handle;
+            if !keep {
+                self.context.delete_vertex_array(Some(vertex_array));
+            }
+            keep
+        });
+        if let Some(program) = self.programs.borrow_mut().get_mut(handle.0).and_then(Option::take) {
+            self.context.delete_program(Some(&program.program));
+        }
+        if self.current_program.get() == Some(handle) {
+            self.current_program.set(None);
+        }
+    }
+
+    fn set_uniform(&self, handle: ProgramHandle, name: &str, value: Uniform) {
+        let programs = self.programs.borrow();
+        let Some(program) = programs.get(handle.0).and_then(Option::as_ref) else { return };
+        if self.set_block_member(program, name, value) {
+            return;
+        }
+        self.use_program(handle, program);
+
+        let mut uniforms = program.uniforms.borrow_mut();
+        let location = uniforms
+            .entry(name.to_string())
+            .or_insert_with(|| self.context.get_uniform_location(&program.program, name));
+        let Some(location) = location.as_ref() else { return };
+
+        let context = &self.context;
+        match value {
+            Uniform::Float(value) => context.uniform1f(Some(location), value),
+            Uniform::Int(value) => context.uniform1i(Some(location), value),
+            Uniform::Bool(value) => context.uniform1i(Some(location), value as i32),
+            Uniform::Vec3(value) => context.uniform3fv_with_f32_array(Some(location), &value),
+            Uniform::Vec4(value) => context.uniform4fv_with_f32_array(Some(location), &value),
+            Uniform::Mat3(value) => context.uniform_matrix3fv_with_f32_array(Some(location), false, &value),
+            Uniform::Mat4(value) => context.uniform_matrix4fv_with_f32_array(Some(location), false, &value),
+            Uniform::Texture(texture) => {
+                // Each sampler keeps the unit it was first given
+                let mut units = program.texture_units.borrow_mut();
+                let next_unit = units.len() as u32;
+                let unit = *units.entry(name.to_string()).or_insert(next_unit);
+
+                let textures = self.textures.borrow();
+                context.active_texture(Gl::TEXTURE0 + unit);
+                context.bind_texture(Gl::TEXTURE_2D, textures.get(texture.0).and_then(Option::as_ref));
+                context.uniform1i(Some(location), unit as i32);
+            }
+        }
+    }
+
+    fn create_texture(&self, source: &TextureSource) -> Result<TextureHandle, String> {
+        let context = &self.context;
+        let texture = context.create_texture().ok_or("Failed to create texture")?;
+        context.bind_texture(Gl::TEXTURE_2D, Some(&texture));
+
+        // Flip so v = 1 is the top row, matching the UVs of the built-in shapes
+        context.pixel_storei(Gl::UNPACK_FLIP_Y_WEBGL, 1);
+        let uploaded = upload_texture(context, source);
+        context.pixel_storei(Gl::UNPACK_FLIP_Y_WEBGL, 0);
+        if let Err(error) = uploaded {
+            context.delete_texture(Some(&texture));
+            return Err(error);
+        }
+
+        // Unlike WebGL 1, any size can be mipmapped and repeated
+        let target = Gl::TEXTURE_2D;
+        context.generate_mipmap(target);
+        context.tex_parameteri(target, Gl::TEXTURE_MIN_FILTER, Gl::LINEAR_MIPMAP_LINEAR as i32);
+        context.tex_parameteri(target, Gl::TEXTURE_MAG_FILTER, Gl::LINEAR as i32);
+        context.tex_parameteri(target, Gl::TEXTURE_WRAP_S, Gl::REPEAT as i32);
+        context.tex_parameteri(target, Gl::TEXTURE_WRAP_T, Gl::CLAMP_TO_EDGE as i32);
+
+        Ok(TextureHandle(insert(&mut self.textures.borrow_mut(), texture)))
+    }
+
+    fn delete_texture(&self, texture: TextureHandle) {
+        if let Some(texture) = self.textures.borrow_mut().get_mut(texture.0).and_then(Option::take) {
+            self.context.delete_texture(Some(&texture));
+        }
+    }
+
+    fn draw(&self, call: &DrawCall) {
+        let programs = self.programs.borrow();
+        let Some(program) = programs.get(call.program.0).and_then(Option::as_ref) else { return };
+        self.use_program(call.program, program);
+        self.upload_blocks();
+        let context = &self.context;
+
+        let key = VertexArrayKey {
+            program: call.program,
+            indices: call.indices,
+            attributes: call
+                .attributes
+                .iter()
+                .map(|a| (a.name.to_string(), a.buffer, a.size, a.stride, a.offset, a.divisor))
+                .collect(),
+        };
+        let mut vertex_arrays = self.vertex_arrays.borrow_mut();
+        match vertex_arrays.entry(key) {
+            Entry::Occupied(entry) => context.bind_vertex_array(Some(entry.get())),
+            Entry::Vacant(entry) => {
+                let Some(vertex_array) = self.create_vertex_array(program, call) else { return };
+                entry.insert(vertex_array);
+            }
+        }
+
+        let mode = match call.primitive {
+            Primitive::Triangles => Gl::TRIANGLES,
+            Primitive::Lines => Gl::LINES,
+            Primitive::LineLoop => Gl::LINE_LOOP,
+            Primitive::LineStrip => Gl::LINE_STRIP,
+            Primitive::Points => Gl::POINTS,
+        };
+        let (first, count, instances) = (call.first as i32, call.count as i32, call.instances as i32);
+        match (call.indices.is_some(), call.instances != 1) {
+            (true, true) => context.draw_elements_instanced_with_i32(mode, count, Gl::UNSIGNED_SHORT, first * 2, instances),
+            (true, false) => context.draw_elements_with_i32(mode, count, Gl::UNSIGNED_SHORT, first * 2),
+            (false, true) => context.draw_arrays_instanced(mode, first, count, instances),
+            (false, false) => context.draw_arrays(mode, first, count),
+        }
+
+        // Index buffer bindings belong to the bound vertex array, so unbind it before any upload
+        context.bind_vertex_array(None);
+    }
+
+    fn supports_instancing(&self) -> bool {
+        true
+    }
+
+    fn clear(&self, color: [f32; 4], depth: bool) {
+        self.context.clear_color(color[0], color[1], color[2], color[3]);
+        if depth {
+            self.context.depth_mask(true);
+            self.context.clear(Gl::COLOR_BUFFER_BIT | Gl::DEPTH_BUFFER_BIT);
+        } else {
+            self.context.clear(Gl::COLOR_BUFFER_BIT);
+        }
+    }
+
+    fn set_viewport(&self, width: u32, height: u32) {
+        self.context.viewport(0, 0, width as i32, height as i32);
+        self.viewport.set((width, height));
+    }
+
+    fn set_depth_test(&self, enabled: bool) {
+        if enabled {
+            self.context.enable(Gl::DEPTH_TEST);
+            self.context.depth_func(Gl::LESS);
+        } else {
+            self.context.disable(Gl::DEPTH_TEST);
+        }
+    }
+
+    fn set_depth_write(&self, enabled: bool) {
+        self.context.depth_mask(enabled);
+    }
+
+    fn set_blending(&self, enabled: bool) {
+        if enabled {
+            self.context.enable(Gl::BLEND);
+            self.context.blend_func(Gl::SRC_ALPHA, Gl::ONE_MINUS_SRC_ALPHA);
+        } else {
+            self.context.disable(Gl::BLEND);
+        }
+    }
+
+    /// Only meaningful before the browser composites the frame, since the drawing buffer
+    /// is not preserved
+    fn read_pixels(&self) -> Vec<u8> {
+        let (width, height) = self.viewport.get();
+        let row = width as usize * 4;
+        let mut pixels = vec![0; row * height as usize];
+        let _ = self.context.read_pixels_with_opt_u8_array(
+            0,
+            0,
+            width as i32,
+            height as i32,
+            Gl::RGBA,
+            Gl::UNSIGNED_BYTE,
+            Some(&mut pixels),
+        );
+
+        // GL rows run bottom to top
+        pixels.chunks_exact(row.max(1)).rev().flatten().copied().collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "webgl2"
+    }
+}
+
+/// Upload `source` to the bound texture
+fn upload_texture(context: &Gl, source: &TextureSource) -> Result<(), String> {
+    let target = Gl::TEXTURE_2D;
+    let rgba = Gl::RGBA;
+    let bytes = Gl::UNSIGNED_BYTE;
+
+    match source {
+        TextureSource::Rgba { width, height, pixels } => {
+            source.validate()?;
+            context
+                .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                    target, 0, rgba as i32, *width as i32, *height as i32, 0, rgba, bytes, Some(pixels),
+                )
+                .map_err(|_| "Failed to upload texture pixels")?;
+        }
+        TextureSource::ImageData(data) => {
+            context
+                .tex_image_2d_with_u32_and_u32_and_image_data(target, 0, rgba as i32, rgba, bytes, data)
+                .map_err(|_| "Failed to upload image data")?;
+        }
+        TextureSource::Image(image) => {
+            context
+                .tex_image_2d_with_u32_and_u32_and_html_image_element(target, 0, rgba as i32, rgba, bytes, image)
+                .map_err(|_| "Failed to upload image")?;
+        }
+    }
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use crate::math::{Mat4, Vec3};
+
 pub struct Camera {
     pub distance: f32,
     pub angle_x: f32,
@@ -8,6 +10,9 @@ pub struct Camera {
     pub transition_progress: f32,
     pub transition_duration: f32,
     pub aspect_ratio: f32,
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
 }
 
 impl Camera {
@@ -22,6 +27,9 @@ impl Camera {
             transition_progress: 1.0,
             transition_duration: 1.0,
             aspect_ratio: 1.333,  // Default 4:3 aspect ratio
+            fov: std::f32::consts::PI / 3.0,  // 60 degree FOV
+            near: 0.1,
+            far: 1000.0,
         }
     }
 
@@ -68,32 +76,63 @@ impl Camera {
         self.aspect_ratio = aspect_ratio;
     }
 
-    pub fn transform_point(&self, point: [f32; 3], center: [f32; 3]) -> ([f32; 2], f32) {
-        // Apply camera translation to center on followed object
-        let x = point[0] - center[0];
-        let y = point[1] - center[1];
-        let z = point[2] - center[2];
-        
-        // Rotate around Y axis (horizontal rotation)
-        let cos_y = self.angle_y.cos();
-        let sin_y = self.angle_y.sin();
-        let x_rotated = x * cos_y - z * sin_y;
-        let z_rotated = x * sin_y + z * cos_y;
-        
-        // Rotate around X axis (vertical rotation)
-        let cos_x = self.angle_x.cos();
-        let sin_x = self.angle_x.sin();
-        let y_rotated = y * cos_x - z_rotated * sin_x;
-        let z_final = y * sin_x + z_rotated * cos_x;
-        
-        // Apply camera distance (zoom)
-        let scale_factor = 1.0 / self.distance;
-        
-        // Project to screen coordinates
-        // Don't apply aspect ratio here - we'll handle it in the matrix
-        let screen_x = x_rotated * scale_factor;
-        let screen_y = y_rotated * scale_factor;
-        
-        ([screen_x, screen_y], z_final)
+    /// Distance from the eye to the followed center. `distance` is the half-height of
+    /// the view at the center, so zooming keeps the same framing as the field of view changes.
+    pub fn eye_distance(&self) -> f32 {
+        self.distance / (self.fov / 2.0).tan()
+    }
+
+    /// World-space position of the eye, orbiting the current center
+    pub fn eye_position(&self) -> Vec3 {
+        let orbit = Mat4::from_rotation_y(self.angle_y) * Mat4::from_rotation_x(-self.angle_x);
+        Vec3::from(self.get_current_center()) + orbit.transform_vector3(Vec3::new(0.0, 0.0, self.eye_distance()))
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        // Move the center to the origin, rotate by the orbit angles, then step back to the eye.
+        // A positive vertical angle tips the scene's far side up, as the original 2D projection did.
+        Mat4::from_translation(Vec3::new(0.0, 0.0, -self.eye_distance()))
+            * Mat4::from_rotation_x(self.angle_x)
+            * Mat4::from_rotation_y(-self.angle_y)
+            * Mat4::from_translation(-Vec3::from(self.get_current_center()))
+    }
+
+    pub fn projection_matrix(&self) -> Mat4 {
+        Mat4::perspective(self.fov, self.aspect_ratio, self.near, self.far)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_view_is_centered_on_the_eye() {
+        let mut camera = Camera::new();
+        camera.set_angles(0.4, -1.2);
+        camera.current_center = [1.0, -2.0, 3.0];
+
+        let eye = camera.view_matrix().transform_point3(camera.eye_position());
+        assert!(eye.length() < 1e-4, "{:?}", eye);
+        let center = camera.view_matrix().transform_point3(Vec3::from(camera.current_center));
+        assert!((center - Vec3::new(0.0, 0.0, -camera.eye_distance())).length() < 1e-4, "{:?}", center);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn angles_rotate_the_same_way_as_the_original_projection() {
+        let mut camera = Camera::new();
+        camera.set_angles(0.3, 0.7);
+        let point = Vec3::new(0.5, 0.2, -0.4);
+        let view = camera.view_matrix().transform_point3(point);
+
+        // The pre-matrix `transform_point`: rotate by -angle_y about Y, then by +angle_x about X
+        let (sin_y, cos_y) = camera.angle_y.sin_cos();
+        let (sin_x, cos_x) = camera.angle_x.sin_cos();
+        let x = point.x * cos_y - point.z * sin_y;
+        let z = point.x * sin_y + point.z * cos_y;
+        let y = point.y * cos_x - z * sin_x;
+        let z = point.y * sin_x + z * cos_x;
+
+        assert!((view - Vec3::new(x, y, z - camera.eye_distance())).length() < 1e-4, "{:?}", view);
+    }
+}
@@ -0,0 +1,204 @@
+//! Star catalogs in CSV form, such as HYG or a CSV export of the Yale Bright Star Catalog.
+//!
+//! Columns are found by header name, case-insensitively, so extra columns and any column
+//! order are fine:
+//!
+//! | Field              | Headers                                 | Units               |
+//! |--------------------|-----------------------------------------|---------------------|
+//! | Right ascension    | `ra`, `ra_hours` or `ra_deg`, `radeg`   | hours or degrees    |
+//! | Declination        | `dec`, `dec_deg`, `dedeg`               | degrees             |
+//! | Magnitude          | `mag`, `vmag`                           | apparent visual     |
+//! | B−V color index    | `ci`, `b-v`, `bv`, `b_v`                | optional            |
+//! | Name               | `proper`, `name`                        | optional            |
+//! | Designation        | `bf`, `bayer`, `designation`            | optional            |
+//! | Hipparcos number   | `hip`                                   | optional            |
+//! | Bright Star number | `hr`                                    | optional            |
+
+use std::f32::consts::PI;
+use crate::math::{Mat3, Vec3};
+
+/// Tilt of Earth's equator against the ecliptic at J2000
+pub const OBLIQUITY_DEGREES: f32 = 23.44;
+
+/// One catalog row
+#[derive(Clone, Debug, PartialEq)]
+pub struct CatalogEntry {
+    pub right_ascension: f32,        // Radians
+    pub declination: f32,            // Radians
+    pub magnitude: f32,
+    pub color_index: Option<f32>,    // B−V
+    pub name: Option<String>,        // Proper name, e.g. "Sirius"
+    pub designation: Option<String>, // Bayer/Flamsteed, or "HR n" when that is all there is
+    pub hip: Option<u32>,
+}
+
+/// Parse catalog CSV, keeping stars at least as bright as `magnitude_limit`. Rows with a
+/// blank position or magnitude are skipped; values that do not parse are errors.
+pub fn parse_csv(source: &str, magnitude_limit: f32) -> Result<Vec<CatalogEntry>, String> {
+    let mut lines = source.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines.next().ok_or("Catalog is empty")?;
+    let header: Vec<String> = split_csv_line(header).iter().map(|h| h.trim().to_lowercase()).collect();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+
+    let (ra_column, ra_in_hours) = match (column(&["ra", "ra_hours", "rahours"]), column(&["ra_deg", "radeg"])) {
+        (Some(hours), _) => (hours, true),
+        (None, Some(degrees)) => (degrees, false),
+        _ => return Err("Catalog has no right ascension column (ra or ra_deg)".to_string()),
+    };
+    let dec_column = column(&["dec", "dec_deg", "dedeg", "de"]).ok_or("Catalog has no declination column (dec)")?;
+    let mag_column = column(&["mag", "vmag"]).ok_or("Catalog has no magnitude column (mag)")?;
+    let ci_column = column(&["ci", "b-v", "bv", "b_v"]);
+    let name_column = column(&["proper", "name"]);
+    let designation_column = column(&["bf", "bayer", "designation"]);
+    let hip_column = column(&["hip"]);
+    let hr_column = column(&["hr"]);
+
+    let mut entries = Vec::new();
+    for (line_number, line) in lines {
+        let fields = split_csv_line(line);
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|i| fields.get(i))
+                .map(|f| f.trim())
+                .filter(|f| !f.is_empty())
+        };
+        let number = |index: usize, what: &str| -> Result<Option<f32>, String> {
+            field(Some(index))
+                .map(|text| text.parse().map_err(|_| format!("Line {}: {} {:?} is not a number", line_number + 1, what, text)))
+                .transpose()
+        };
+
+        // Rows with a blank position or magnitude (a few BSC entries) cannot be placed
+        let (Some(magnitude), Some(ra), Some(declination)) = (
+            number(mag_column, "magnitude")?,
+            number(ra_column, "right ascension")?,
+            number(dec_column, "declination")?,
+        ) else {
+            continue;
+        };
+        // The Sun appears in HYG as row zero; it is not part of the night sky
+        if magnitude > magnitude_limit || magnitude < -20.0 {
+            continue;
+        }
+
+        let right_ascension = if ra_in_hours { ra * PI / 12.0 } else { ra.to_radians() };
+        let declination = declination.to_radians();
+
+        let hr = field(hr_column).and_then(|hr| hr.parse::<u32>().ok());
+        entries.push(CatalogEntry {
+            right_ascension,
+            declination,
+            magnitude,
+            color_index: field(ci_column).and_then(|ci| ci.parse().ok()),
+            name: field(name_column).map(str::to_string),
+            designation: field(designation_column).map(str::to_string).or_else(|| hr.map(|hr| format!("HR {}", hr))),
+            hip: field(hip_column).and_then(|hip| hip.parse().ok()),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Unit direction in engine space for equatorial coordinates (radians). Rotates by the
+/// obliquity into the ecliptic frame, then maps ecliptic (x, y, z) to engine (x, z, -y)
+/// like the orbits do, so the sky lines up with the planets.
+pub fn equatorial_to_engine(right_ascension: f32, declination: f32) -> Vec3 {
+    let (sin_ra, cos_ra) = right_ascension.sin_cos();
+    let (sin_dec, cos_dec) = declination.sin_cos();
+    let (x, y, z) = (cos_dec * cos_ra, cos_dec * sin_ra, sin_dec);
+
+    let (sin_e, cos_e) = OBLIQUITY_DEGREES.to_radians().sin_cos();
+    let ecliptic_y = y * cos_e + z * sin_e;
+    let ecliptic_z = -y * sin_e + z * cos_e;
+
+    Vec3::new(x, ecliptic_z, -ecliptic_y)
+}
+
+/// Rotation taking engine-space directions to galactic coordinates: +X toward the
+/// galactic center, +Z toward the north galactic pole
+pub fn engine_to_galactic() -> Mat3 {
+    // Engine to ecliptic undoes the (x, z, -y) mapping; the columns are images of engine X, Y, Z
+    let engine_to_ecliptic = Mat3::from_cols(Vec3::X, Vec3::Z, -Vec3::Y);
+
+    let (sin_e, cos_e) = OBLIQUITY_DEGREES.to_radians().sin_cos();
+    let ecliptic_to_equatorial = Mat3::from_cols(Vec3::X, Vec3::new(0.0, cos_e, sin_e), Vec3::new(0.0, -sin_e, cos_e));
+
+    // J2000 equatorial to galactic (Hipparcos definition), written as columns
+    let equatorial_to_galactic = Mat3::from_cols(
+        Vec3::new(-0.054_875_6, 0.494_109_4, -0.867_666_1),
+        Vec3::new(-0.873_437_1, -0.444_829_6, -0.198_076_4),
+        Vec3::new(-0.483_835, 0.746_982_2, 0.455_983_8),
+    );
+
+    equatorial_to_galactic * ecliptic_to_equatorial * engine_to_ecliptic
+}
+
+/// Split one CSV line, honouring double quotes (with `""` as an escaped quote)
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quoted_fields_in_any_column_order() {
+        let csv = "\
+Mag,Proper,RA,Dec,ci,hip,bf
+-1.44,\"Sirius, the Dog Star\",6.7525,-16.7161,0.009,32349,9Alp CMa
+0.03,\"Vega \"\"Wega\"\"\",18.6156,38.7837,,91262,3Alp Lyr
+";
+        let entries = parse_csv(csv, 6.5).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name.as_deref(), Some("Sirius, the Dog Star"));
+        assert_eq!(entries[0].designation.as_deref(), Some("9Alp CMa"));
+        assert_eq!(entries[0].hip, Some(32349));
+        assert!((entries[0].right_ascension - 6.7525 * PI / 12.0).abs() < 1e-6);
+        assert!((entries[0].declination - (-16.7161f32).to_radians()).abs() < 1e-6);
+        assert_eq!(entries[1].name.as_deref(), Some("Vega \"Wega\""));
+        assert_eq!(entries[1].color_index, None);
+    }
+
+    #[test]
+    fn drops_stars_fainter_than_the_limit_and_the_sun() {
+        let csv = "ra_deg,dec,vmag,hr\n0.0,0.0,-26.7,\n10.0,5.0,4.0,15\n20.0,-5.0,7.2,16\n";
+        let entries = parse_csv(csv, 6.5).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert!((entries[0].right_ascension - 10f32.to_radians()).abs() < 1e-6);
+        assert_eq!(entries[0].designation.as_deref(), Some("HR 15"));
+    }
+
+    #[test]
+    fn reports_missing_columns_and_values() {
+        assert!(parse_csv("", 6.5).unwrap_err().contains("empty"));
+        assert!(parse_csv("dec,mag\n1,2\n", 6.5).unwrap_err().contains("right ascension"));
+        assert!(parse_csv("ra,mag\n1,2\n", 6.5).unwrap_err().contains("declination"));
+        assert!(parse_csv("ra,dec\n1,2\n", 6.5).unwrap_err().contains("magnitude"));
+
+        // Blank required fields skip the row; values that do not parse are errors
+        assert_eq!(parse_csv("ra,dec,mag\n1,2,3\n1,,3\n,2,3\n1,2,\n", 6.5).unwrap().len(), 1);
+        let error = parse_csv("ra,dec,mag\n1,2,3\n1,north,3\n", 6.5).unwrap_err();
+        assert!(error.contains("Line 3") && error.contains("declination"), "{}", error);
+    }
+}
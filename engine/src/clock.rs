@@ -0,0 +1,83 @@
+/// Julian day of the J2000.0 epoch (2000-01-01 12:00 TT), the epoch of the built-in orbital elements
+pub const J2000: f64 = 2_451_545.0;
+
+/// Julian day of the Unix epoch (1970-01-01 00:00 UTC)
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2_440_587.5;
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+
+/// Simulated calendar time, in Julian days
+pub struct SimulationClock {
+    julian_day: f64,
+    pub time_scale: f64, // Simulated days per real second; negative runs backwards
+    pub paused: bool,
+}
+
+impl SimulationClock {
+    pub fn new(julian_day: f64) -> Self {
+        Self {
+            julian_day,
+            time_scale: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn julian_day(&self) -> f64 {
+        self.julian_day
+    }
+
+    pub fn days_since_j2000(&self) -> f64 {
+        self.julian_day - J2000
+    }
+
+    pub fn set_julian_day(&mut self, julian_day: f64) {
+        self.julian_day = julian_day;
+    }
+
+    /// Advance by `real_seconds` of wall-clock time, scaled by `time_scale`, unless paused
+    pub fn advance(&mut self, real_seconds: f64) {
+        if !self.paused {
+            self.julian_day += real_seconds * self.time_scale;
+        }
+    }
+
+    /// Move by exactly `days`, even while paused
+    pub fn step(&mut self, days: f64) {
+        self.julian_day += days;
+    }
+}
+
+pub fn julian_day_from_unix_millis(millis: f64) -> f64 {
+    UNIX_EPOCH_JULIAN_DAY + millis / MILLIS_PER_DAY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_negative_time_scale_runs_backwards() {
+        let mut clock = SimulationClock::new(J2000);
+        clock.time_scale = -10.0;
+        clock.advance(0.5);
+        assert_eq!(clock.julian_day(), J2000 - 5.0);
+    }
+
+    #[test]
+    fn pausing_stops_advance_but_not_step() {
+        let mut clock = SimulationClock::new(J2000);
+        clock.paused = true;
+        clock.advance(3.0);
+        assert_eq!(clock.julian_day(), J2000);
+
+        clock.step(-1.5);
+        assert_eq!(clock.julian_day(), J2000 - 1.5);
+    }
+
+    #[test]
+    fn dates_line_up_with_the_epochs() {
+        assert_eq!(julian_day_from_unix_millis(0.0), 2_440_587.5);
+        assert_eq!(SimulationClock::new(J2000).days_since_j2000(), 0.0);
+        // 2000-01-01 12:00 UTC, ignoring the TT offset
+        assert_eq!(julian_day_from_unix_millis(946_728_000_000.0), J2000);
+    }
+}
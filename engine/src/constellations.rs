@@ -0,0 +1,134 @@
+//! Constellation stick figures and IAU boundaries for the sky overlay.
+//!
+//! Stick figures use Stellarium's `constellationship.fab` layout: an abbreviation, the
+//! number of segments, then two Hipparcos numbers per segment, all whitespace separated.
+//!
+//! ```text
+//! Ori 2  26727 26311  26311 25930
+//! ```
+//!
+//! Boundaries are one vertex per line, right ascension in hours, declination in degrees
+//! and the constellation abbreviation, separated by whitespace or `|` as in the J2000
+//! table of Davenhall & Leggett (VizieR VI/49). Consecutive vertices with the same
+//! abbreviation form one closed outline. Lines starting with `#` are comments everywhere.
+
+use std::f32::consts::PI;
+
+/// One constellation's stick figure, as segments between Hipparcos stars
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstellationFigure {
+    pub abbreviation: String,
+    pub segments: Vec<(u32, u32)>,
+}
+
+/// One constellation's boundary outline
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstellationBoundary {
+    pub abbreviation: String,
+    pub vertices: Vec<(f32, f32)>, // Right ascension and declination, in radians
+}
+
+pub fn parse_figures(source: &str) -> Result<Vec<ConstellationFigure>, String> {
+    let mut figures = Vec::new();
+
+    for (line_number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let abbreviation = fields.next().unwrap_or_default().to_string();
+        let count: usize = fields
+            .next()
+            .and_then(|count| count.parse().ok())
+            .ok_or_else(|| format!("Line {}: expected a segment count after {:?}", line_number + 1, abbreviation))?;
+        let stars = fields
+            .map(|hip| hip.parse::<u32>().map_err(|_| format!("Line {}: {:?} is not a Hipparcos number", line_number + 1, hip)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if stars.len() != count * 2 {
+            return Err(format!("Line {}: {} segments need {} star numbers, found {}", line_number + 1, count, count * 2, stars.len()));
+        }
+
+        let segments = stars.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect();
+        figures.push(ConstellationFigure { abbreviation, segments });
+    }
+
+    Ok(figures)
+}
+
+pub fn parse_boundaries(source: &str) -> Result<Vec<ConstellationBoundary>, String> {
+    let mut boundaries: Vec<ConstellationBoundary> = Vec::new();
+
+    for (line_number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(|c: char| c == '|' || c.is_whitespace()).filter(|f| !f.is_empty()).collect();
+        let [ra, dec, abbreviation, ..] = fields[..] else {
+            return Err(format!("Line {}: expected right ascension, declination and constellation", line_number + 1));
+        };
+        let number = |text: &str| {
+            text.parse::<f32>().map_err(|_| format!("Line {}: {:?} is not a number", line_number + 1, text))
+        };
+        let vertex = (number(ra)? * PI / 12.0, number(dec)?.to_radians());
+
+        match boundaries.last_mut() {
+            Some(boundary) if boundary.abbreviation.eq_ignore_ascii_case(abbreviation) => boundary.vertices.push(vertex),
+            _ => boundaries.push(ConstellationBoundary { abbreviation: abbreviation.to_string(), vertices: vec![vertex] }),
+        }
+    }
+
+    Ok(boundaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stick_figures() {
+        let figures = parse_figures("# Orion's belt\nOri 2  26727 26311  26311 25930\n\nCru 1 60718 61084\n").unwrap();
+
+        assert_eq!(figures.len(), 2);
+        assert_eq!(figures[0].abbreviation, "Ori");
+        assert_eq!(figures[0].segments, [(26727, 26311), (26311, 25930)]);
+        assert_eq!(figures[1].segments, [(60718, 61084)]);
+    }
+
+    #[test]
+    fn rejects_malformed_stick_figures() {
+        assert!(parse_figures("Ori two 1 2").unwrap_err().contains("segment count"));
+        assert!(parse_figures("Ori 1 1 x").unwrap_err().contains("Hipparcos"));
+        let error = parse_figures("Cru 1 1 2\nOri 2 1 2 3").unwrap_err();
+        assert!(error.starts_with("Line 2") && error.contains("need 4"), "{}", error);
+    }
+
+    #[test]
+    fn groups_boundary_vertices_by_constellation() {
+        let source = "\
+# RA (h) | Dec (deg) | constellation
+22.000|+35.000|AND
+ 2.500|+53.000|AND
+ 6.000 -10.000 ori
+ 6.000  22.000 ORI
+";
+        let boundaries = parse_boundaries(source).unwrap();
+
+        assert_eq!(boundaries.len(), 2);
+        assert_eq!(boundaries[0].abbreviation, "AND");
+        assert_eq!(boundaries[0].vertices.len(), 2);
+        assert_eq!(boundaries[1].vertices.len(), 2);
+        let (ra, dec) = boundaries[0].vertices[0];
+        assert!((ra - 22.0 * PI / 12.0).abs() < 1e-6);
+        assert!((dec - 35f32.to_radians()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rejects_malformed_boundaries() {
+        assert!(parse_boundaries("22.0 35.0").unwrap_err().contains("expected right ascension"));
+        assert!(parse_boundaries("22.0 north AND").unwrap_err().contains("not a number"));
+    }
+}
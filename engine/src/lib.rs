@@ -1,24 +1,44 @@
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::WebGlRenderingContext;
+use web_sys::{WebGl2RenderingContext, WebGlRenderingContext};
 
+pub mod backend;
 mod shaders;
-mod math;
+pub mod math;
 mod renderer;
-mod shapes;
+mod texture;
+pub mod shapes;
 mod solar_system;
+mod orbit;
+mod noise;
+mod random;
+mod procedural;
+mod clock;
+mod scene;
+mod trail;
 mod camera;
 mod rendering;
 mod starfield;
+mod catalog;
+mod constellations;
 
-use shaders::{compile_shader, link_program, VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE, STARFIELD_VERTEX_SHADER, STARFIELD_FRAGMENT_SHADER};
+use backend::{GraphicsBackend, ProgramHandle, WebGl2Backend, WebGlBackend};
+use shaders::ShaderKind;
 use renderer::Renderer;
-use solar_system::SolarSystem;
+use solar_system::{CelestialBody, SolarSystem};
 use math::create_rotation_matrix_2d;
 use shapes::{Triangle, Rectangle, RenderableShape};
 use camera::Camera;
-use rendering::SolarSystemRenderer;
+use rendering::{ConstellationRenderer, Lighting, OrbitRenderer, RingRenderer, SkyRenderer, SolarSystemRenderer, TrailRenderer};
 use starfield::Starfield;
+use scene::SystemDescription;
+use texture::TextureSource;
+
+/// Seed for the default sky, so every page load shows the same stars
+const STARFIELD_SEED: u64 = 0x5EED_57A2;
+
+/// Radius of the sphere the stars and constellation overlays are drawn on
+const SKY_RADIUS: f32 = 500.0;
 
 #[wasm_bindgen]
 pub struct GraphicsEngine {
@@ -32,84 +52,65 @@ pub struct GraphicsEngine {
     camera: Camera,
     solar_system: SolarSystem,
     starfield: Starfield,
-    starfield_program: web_sys::WebGlProgram,
+    starfield_program: ProgramHandle,
+    triangle: Triangle,
+    rectangle: Rectangle,
+    solar_system_renderer: SolarSystemRenderer,
+    lighting: Lighting,
+    ring_renderer: RingRenderer,
+    orbit_renderer: OrbitRenderer,
+    show_orbits: bool,
+    trail_renderer: TrailRenderer,
+    show_trails: bool,
+    constellation_renderer: ConstellationRenderer,
+    show_constellations: bool,
+    show_constellation_boundaries: bool,
+    sky_renderer: SkyRenderer,
+    show_milky_way: bool,
 }
 
 #[wasm_bindgen]
 impl GraphicsEngine {
+    /// Draw on the canvas with id `canvas_id`. Pass `webgl2: true` to use a WebGL 2 context
+    /// where the browser has one and compiles the engine's GLSL ES 3.00 shaders; otherwise
+    /// WebGL 1 is used, with the reason logged to the console.
     #[wasm_bindgen(constructor)]
-    pub fn new(canvas_id: &str) -> Result<GraphicsEngine, JsValue> {
+    pub fn new(canvas_id: &str, webgl2: Option<bool>) -> Result<GraphicsEngine, JsValue> {
         let document = web_sys::window().unwrap().document().unwrap();
         let canvas = document.get_element_by_id(canvas_id).unwrap();
         let canvas: web_sys::HtmlCanvasElement = canvas
             .dyn_into::<web_sys::HtmlCanvasElement>()
             .map_err(|_| JsValue::from_str("Failed to cast to canvas"))?;
 
-        let context = canvas
-            .get_context("webgl")
-            .unwrap()
-            .unwrap()
-            .dyn_into::<WebGlRenderingContext>()
-            .unwrap();
-        
-        // Set initial viewport
-        let width = canvas.width() as i32;
-        let height = canvas.height() as i32;
-        context.viewport(0, 0, width, height);
-
-        let vert_shader = compile_shader(
-            &context,
-            WebGlRenderingContext::VERTEX_SHADER,
-            VERTEX_SHADER_SOURCE,
-        ).map_err(|e| JsValue::from_str(&e))?;
-
-        let frag_shader = compile_shader(
-            &context,
-            WebGlRenderingContext::FRAGMENT_SHADER,
-            FRAGMENT_SHADER_SOURCE,
-        ).map_err(|e| JsValue::from_str(&e))?;
-
-        let program = link_program(&context, &vert_shader, &frag_shader).map_err(|e| JsValue::from_str(&e))?;
-        
-        // Create starfield shader program
-        let star_vert_shader = compile_shader(
-            &context,
-            WebGlRenderingContext::VERTEX_SHADER,
-            STARFIELD_VERTEX_SHADER,
-        ).map_err(|e| JsValue::from_str(&e))?;
-
-        let star_frag_shader = compile_shader(
-            &context,
-            WebGlRenderingContext::FRAGMENT_SHADER,
-            STARFIELD_FRAGMENT_SHADER,
-        ).map_err(|e| JsValue::from_str(&e))?;
-
-        let starfield_program = link_program(&context, &star_vert_shader, &star_frag_shader)
-            .map_err(|e| JsValue::from_str(&e))?;
-        
-        context.use_program(Some(&program));
+        let webgl2_backend = if webgl2.unwrap_or(false) {
+            create_webgl2_backend(&document, &canvas).unwrap_or_else(|error| {
+                web_sys::console::warn_1(&JsValue::from_str(&format!("Falling back to WebGL 1: {error}")));
+                None
+            })
+        } else {
+            None
+        };
+        let backend: Box<dyn GraphicsBackend> = match webgl2_backend {
+            Some(backend) => Box::new(backend),
+            None => {
+                let context = canvas
+                    .get_context("webgl")
+                    .ok()
+                    .flatten()
+                    .and_then(|context| context.dyn_into::<WebGlRenderingContext>().ok())
+                    .ok_or_else(|| JsValue::from_str("Failed to get a WebGL context"))?;
+                Box::new(WebGlBackend::new(context))
+            }
+        };
 
-        let renderer = Renderer::new(context.clone(), program);
-        
-        // Create starfield with 5000 stars much further away at radius 500
-        let mut starfield = Starfield::new(5000, 500.0);
-        starfield.init_buffers(&context)?;
+        let mut engine = Self::with_backend(backend, canvas.width(), canvas.height())
+            .map_err(|e| JsValue::from_str(&e))?;
 
-        Ok(GraphicsEngine {
-            renderer,
-            rotation: 0.0,
-            scale: 1.0,
-            color: [1.0, 1.0, 1.0],
-            translation: [0.0, 0.0],
-            background_color: [0.0, 0.0, 0.0, 1.0],
-            wireframe_mode: false,
-            camera: Camera::new(),
-            solar_system: SolarSystem::new(),
-            starfield,
-            starfield_program,
-        })
+        // Start at the current date
+        engine.solar_system.set_date(clock::julian_day_from_unix_millis(js_sys::Date::now()));
+        Ok(engine)
     }
-
+    
     pub fn set_rotation(&mut self, rotation: f32) {
         self.rotation = rotation;
     }
@@ -145,12 +146,11 @@ impl GraphicsEngine {
     pub fn render(&self) {
         self.renderer.clear(self.background_color);
         
-        let triangle = Triangle::new();
         let matrix = create_rotation_matrix_2d(self.rotation, self.scale, self.translation);
-        triangle.render(
-            &self.renderer.context, 
-            &self.renderer.program, 
-            [0.0, 0.0, 0.0], 
+        self.triangle.render(
+            self.renderer.backend.as_ref(), 
+            self.renderer.program, 
+            &self.renderer.meshes, 
             self.color, 
             &matrix, 
             self.wireframe_mode
@@ -160,12 +160,11 @@ impl GraphicsEngine {
     pub fn render_cube(&self) {
         self.renderer.clear_3d(self.background_color);
         
-        let rectangle = Rectangle::new();
         let matrix = create_rotation_matrix_2d(self.rotation, self.scale, self.translation);
-        rectangle.render(
-            &self.renderer.context, 
-            &self.renderer.program, 
-            [0.0, 0.0, 0.0], 
+        self.rectangle.render(
+            self.renderer.backend.as_ref(), 
+            self.renderer.program, 
+            &self.renderer.meshes, 
             self.color, 
             &matrix, 
             self.wireframe_mode
@@ -188,57 +187,294 @@ impl GraphicsEngine {
         self.camera.update_transition(delta_time, target_position);
     }
     
+    /// Simulated days per real second; negative runs backwards
     pub fn set_time_scale(&mut self, scale: f32) {
         self.solar_system.set_time_scale(scale);
     }
     
+    /// Set the simulated date, in Julian days (2451545.0 is 2000-01-01 12:00)
+    pub fn set_date(&mut self, julian_day: f64) {
+        self.solar_system.set_date(julian_day);
+    }
+    
+    pub fn get_date(&self) -> f64 {
+        self.solar_system.clock.julian_day()
+    }
+    
+    pub fn set_paused(&mut self, paused: bool) {
+        self.solar_system.clock.paused = paused;
+    }
+    
+    pub fn is_paused(&self) -> bool {
+        self.solar_system.clock.paused
+    }
+    
+    /// Step the simulation by `days` (negative steps backwards); works while paused
+    pub fn step_days(&mut self, days: f64) {
+        self.solar_system.step(days);
+    }
+    
     pub fn render_solar_system(&self) {
-        use crate::math::{create_view_matrix, create_perspective_matrix};
-        
         self.renderer.clear_3d(self.background_color);
         
         // Enable depth testing and blending for stars
-        self.renderer.context.enable(WebGlRenderingContext::DEPTH_TEST);
-        self.renderer.context.enable(WebGlRenderingContext::BLEND);
-        self.renderer.context.blend_func(WebGlRenderingContext::SRC_ALPHA, WebGlRenderingContext::ONE_MINUS_SRC_ALPHA);
+        self.renderer.backend.set_depth_test(true);
+        self.renderer.backend.set_blending(true);
         
-        // Render starfield first (behind everything)
-        self.renderer.context.use_program(Some(&self.starfield_program));
+        // The Milky Way is painted onto the background, behind everything
+        if self.show_milky_way {
+            self.sky_renderer.render(&self.camera, &self.renderer);
+        }
         
-        // Create view and projection matrices for starfield
-        let view_matrix = create_view_matrix(
-            self.camera.get_current_center(),
-            self.camera.angle_x,
-            self.camera.angle_y,
-        );
-        let projection_matrix = create_perspective_matrix(
-            std::f32::consts::PI / 3.0,  // 60 degree FOV
-            self.camera.aspect_ratio,
-            0.1,
-            1000.0,
-        );
+        // Render starfield next, over the sky
+        // Same view and projection as the planets
+        let view_matrix = self.camera.view_matrix().to_cols_array();
+        let projection_matrix = self.camera.projection_matrix().to_cols_array();
         
         // Render the starfield
-        let _ = self.starfield.render(
-            &self.renderer.context,
-            &self.starfield_program,
+        self.starfield.render(
+            self.renderer.backend.as_ref(),
+            self.starfield_program,
             &view_matrix,
             &projection_matrix,
         );
         
-        // Switch back to main program for solar system
-        self.renderer.context.use_program(Some(&self.renderer.program));
+        // Constellation overlays sit on the same sphere as the stars
+        if self.show_constellations || self.show_constellation_boundaries {
+            self.constellation_renderer.render(
+                &self.camera,
+                &self.renderer,
+                self.show_constellations,
+                self.show_constellation_boundaries,
+            );
+        }
+        
+        // Render solar system with its own lit program
+        self.solar_system_renderer.render(
+            &self.solar_system,
+            &self.camera,
+            &self.renderer,
+            &self.lighting,
+            self.wireframe_mode,
+        );
         
-        // Render solar system
-        SolarSystemRenderer::render(
+        // Rings, orbits and trails are translucent, so they go after the opaque bodies
+        self.ring_renderer.render(
             &self.solar_system,
             &self.camera,
             &self.renderer,
+            &self.lighting,
             self.wireframe_mode,
         );
+        if self.show_orbits {
+            self.orbit_renderer.render(&self.solar_system, &self.camera, &self.renderer);
+        }
+        if self.show_trails {
+            self.trail_renderer.render(&self.solar_system, &self.camera, &self.renderer);
+        }
         
         // Disable blending
-        self.renderer.context.disable(WebGlRenderingContext::BLEND);
+        self.renderer.backend.set_blending(false);
+    }
+    
+    pub fn set_light_color(&mut self, r: f32, g: f32, b: f32) {
+        self.lighting.color = [r, g, b];
+    }
+    
+    pub fn set_light_intensity(&mut self, intensity: f32) {
+        self.lighting.intensity = intensity;
+    }
+    
+    pub fn set_ambient_light(&mut self, ambient: f32) {
+        self.lighting.ambient = ambient;
+    }
+    
+    pub fn set_specular(&mut self, strength: f32, shininess: f32) {
+        self.lighting.specular = strength;
+        self.lighting.shininess = shininess;
+    }
+    
+    /// Replace the planetary system with one described in JSON. On failure the current
+    /// system is kept and a `{ kind, message, issues? }` object is thrown.
+    pub fn load_system_json(&mut self, source: &str) -> Result<(), JsValue> {
+        let description = SystemDescription::from_json(source)?;
+        self.load_system(&description)
+    }
+    
+    /// TOML counterpart of `load_system_json`
+    pub fn load_system_toml(&mut self, source: &str) -> Result<(), JsValue> {
+        let description = SystemDescription::from_toml(source)?;
+        self.load_system(&description)
+    }
+    
+    pub fn set_orbits_visible(&mut self, visible: bool) {
+        self.show_orbits = visible;
+    }
+    
+    pub fn set_orbit_visible(&mut self, index: usize, visible: bool) {
+        if let Some(body) = self.solar_system.bodies.get_mut(index) {
+            body.show_orbit = visible;
+        }
+    }
+    
+    pub fn set_orbit_color(&mut self, index: usize, r: f32, g: f32, b: f32, a: f32) {
+        if let Some(body) = self.solar_system.bodies.get_mut(index) {
+            body.orbit_color = [r, g, b, a];
+        }
+    }
+    
+    pub fn set_trails_visible(&mut self, visible: bool) {
+        self.show_trails = visible;
+    }
+    
+    /// How many simulated days of motion body `index` leaves behind; 0 turns its trail off
+    pub fn set_trail_length(&mut self, index: usize, days: f64) {
+        if let Some(body) = self.solar_system.bodies.get_mut(index) {
+            body.trail_length = days.max(0.0);
+            body.trail.clear();
+        }
+    }
+    
+    /// Axial tilt in degrees and sidereal rotation period in days for body `index`
+    pub fn set_body_rotation(&mut self, index: usize, axial_tilt: f32, rotation_period: f64) {
+        if let Some(body) = self.solar_system.bodies.get_mut(index) {
+            body.axial_tilt = axial_tilt.to_radians();
+            body.rotation_period = rotation_period;
+        }
+    }
+    
+    /// Use a loaded `<img>` as body `index`'s surface map (equirectangular, north up)
+    pub fn set_body_texture_image(&mut self, index: usize, image: &web_sys::HtmlImageElement) -> Result<(), JsValue> {
+        self.set_body_texture(index, &TextureSource::Image(image))
+    }
+    
+    /// Use canvas `ImageData` as body `index`'s surface map
+    pub fn set_body_texture_data(&mut self, index: usize, data: &web_sys::ImageData) -> Result<(), JsValue> {
+        self.set_body_texture(index, &TextureSource::ImageData(data))
+    }
+    
+    /// Use raw RGBA bytes, top row first, as body `index`'s surface map
+    pub fn set_body_texture_rgba(&mut self, index: usize, width: u32, height: u32, pixels: &[u8]) -> Result<(), JsValue> {
+        self.set_body_texture(index, &TextureSource::Rgba { width, height, pixels })
+    }
+    
+    /// Go back to drawing body `index` in its flat color
+    pub fn clear_body_texture(&mut self, index: usize) {
+        self.solar_system_renderer.set_diffuse_map(&mut self.renderer, index, None);
+    }
+    
+    /// Replace the background stars with the sky generated from `seed`
+    pub fn set_starfield_seed(&mut self, seed: u32) -> Result<(), JsValue> {
+        self.starfield.dispose(self.renderer.backend.as_ref());
+        self.starfield = Starfield::new(seed as u64, 5000, SKY_RADIUS);
+        self.starfield.init_buffers(self.renderer.backend.as_ref())
+            .map_err(|e| JsValue::from_str(&e))?;
+        self.constellation_renderer.rebuild_figures(&mut self.renderer, &self.starfield)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+    
+    /// Replace the random sky with stars from a catalog CSV (HYG or Yale Bright Star
+    /// columns), keeping those at least as bright as `magnitude_limit`. Returns the star count.
+    pub fn load_star_catalog(&mut self, csv: &str, magnitude_limit: f32) -> Result<usize, JsValue> {
+        let entries = catalog::parse_csv(csv, magnitude_limit).map_err(|e| JsValue::from_str(&e))?;
+        self.starfield.dispose(self.renderer.backend.as_ref());
+        self.starfield = Starfield::from_catalog(&entries, SKY_RADIUS);
+        self.starfield.init_buffers(self.renderer.backend.as_ref())
+            .map_err(|e| JsValue::from_str(&e))?;
+        self.constellation_renderer.rebuild_figures(&mut self.renderer, &self.starfield)
+            .map_err(|e| JsValue::from_str(&e))?;
+        Ok(self.starfield.stars().len())
+    }
+    
+    /// Load constellation stick figures (Stellarium `constellationship.fab` layout). They
+    /// connect stars by Hipparcos number, so they only appear once a catalog is loaded.
+    /// Returns the number of constellations.
+    pub fn load_constellation_lines(&mut self, source: &str) -> Result<usize, JsValue> {
+        let figures = constellations::parse_figures(source).map_err(|e| JsValue::from_str(&e))?;
+        let count = figures.len();
+        self.constellation_renderer.set_figures(&mut self.renderer, figures, &self.starfield)
+            .map_err(|e| JsValue::from_str(&e))?;
+        Ok(count)
+    }
+    
+    /// Load IAU constellation boundaries (RA hours, Dec degrees, abbreviation per vertex).
+    /// Returns the number of outlines.
+    pub fn load_constellation_boundaries(&mut self, source: &str) -> Result<usize, JsValue> {
+        let boundaries = constellations::parse_boundaries(source).map_err(|e| JsValue::from_str(&e))?;
+        self.constellation_renderer.set_boundaries(&mut self.renderer, &boundaries, SKY_RADIUS)
+            .map_err(|e| JsValue::from_str(&e))?;
+        Ok(boundaries.len())
+    }
+    
+    pub fn set_constellations_visible(&mut self, visible: bool) {
+        self.show_constellations = visible;
+    }
+    
+    pub fn set_constellation_boundaries_visible(&mut self, visible: bool) {
+        self.show_constellation_boundaries = visible;
+    }
+    
+    pub fn set_constellation_color(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        self.constellation_renderer.figure_color = [r, g, b, a];
+    }
+    
+    pub fn set_constellation_boundary_color(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        self.constellation_renderer.boundary_color = [r, g, b, a];
+    }
+    
+    pub fn set_milky_way_visible(&mut self, visible: bool) {
+        self.show_milky_way = visible;
+    }
+    
+    /// Brightness of the Milky Way and nebulae; keep it low so the planets stand out
+    pub fn set_milky_way_intensity(&mut self, intensity: f32) {
+        self.sky_renderer.intensity = intensity.max(0.0);
+    }
+    
+    pub fn set_nebulae_visible(&mut self, visible: bool) {
+        self.sky_renderer.show_nebulae = visible;
+    }
+    
+    pub fn get_star_count(&self) -> usize {
+        self.starfield.stars().len()
+    }
+    
+    pub fn get_star_name(&self, index: usize) -> Option<String> {
+        self.starfield.stars().get(index).and_then(|star| star.name.clone())
+    }
+    
+    pub fn get_star_designation(&self, index: usize) -> Option<String> {
+        self.starfield.stars().get(index).and_then(|star| star.designation.clone())
+    }
+    
+    pub fn get_star_magnitude(&self, index: usize) -> Option<f32> {
+        self.starfield.stars().get(index).and_then(|star| star.magnitude)
+    }
+    
+    /// Hipparcos catalog number of star `index`, when the catalog had one
+    pub fn get_star_hip(&self, index: usize) -> Option<u32> {
+        self.starfield.stars().get(index).and_then(|star| star.hip)
+    }
+    
+    /// Where star `index` is drawn, as [x, y] in normalized device coordinates; None when
+    /// it is behind the camera
+    pub fn get_star_screen_position(&self, index: usize) -> Option<Vec<f32>> {
+        let star = self.starfield.stars().get(index)?;
+        let clip = self.camera.projection_matrix() * self.camera.view_matrix() * math::Vec3::from(star.position).extend(1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        let ndc = clip.perspective_divide();
+        Some(vec![ndc.x, ndc.y])
+    }
+    
+    /// Index of the star nearest (`x`, `y`) in normalized device coordinates within
+    /// `max_distance`, or -1
+    pub fn pick_star(&self, x: f32, y: f32, max_distance: f32) -> i32 {
+        let view_projection = self.camera.projection_matrix() * self.camera.view_matrix();
+        self.starfield
+            .pick(&view_projection, x, y, max_distance)
+            .map_or(-1, |index| index as i32)
     }
     
     pub fn get_planet_count(&self) -> usize {
@@ -251,6 +487,14 @@ impl GraphicsEngine {
             .unwrap_or_default()
     }
     
+    /// Index of the body this one orbits, or -1 for bodies orbiting the origin
+    pub fn get_planet_parent(&self, index: usize) -> i32 {
+        self.solar_system.get_body(index)
+            .and_then(|body| body.parent)
+            .map(|parent| parent as i32)
+            .unwrap_or(-1)
+    }
+    
     pub fn set_follow_planet(&mut self, index: i32) {
         if index < 0 {
             self.camera.follow_target(None);
@@ -265,8 +509,182 @@ impl GraphicsEngine {
     
     pub fn resize_canvas(&mut self, width: u32, height: u32) {
         // Update WebGL viewport to match canvas size
-        self.renderer.context.viewport(0, 0, width as i32, height as i32);
+        self.renderer.backend.set_viewport(width, height);
         // Store aspect ratio for reference (not used for scaling)
         self.camera.set_aspect_ratio(width as f32 / height as f32);
     }
-}
\ No newline at end of file
+    
+    /// The last frame as RGBA bytes, top row first. Call straight after rendering, before
+    /// the browser presents the frame.
+    pub fn read_pixels(&self) -> Vec<u8> {
+        self.renderer.backend.read_pixels()
+    }
+    
+    /// Which backend draws the frames: `"webgl2"`, `"webgl"`, or another for native engines
+    pub fn get_backend_name(&self) -> String {
+        self.renderer.backend.name().to_string()
+    }
+    
+    /// Free every GPU buffer, texture and program owned by the engine. Nothing is drawn
+    /// after this call.
+    pub fn dispose(&mut self) {
+        self.triangle.free(self.renderer.backend.as_ref(), &mut self.renderer.meshes);
+        self.rectangle.free(self.renderer.backend.as_ref(), &mut self.renderer.meshes);
+        self.solar_system_renderer.free(&mut self.renderer);
+        self.ring_renderer.free(&mut self.renderer);
+        self.orbit_renderer.free(&mut self.renderer);
+        self.trail_renderer.free(&self.renderer);
+        self.constellation_renderer.free(&mut self.renderer);
+        self.sky_renderer.free(&mut self.renderer);
+        self.starfield.dispose(self.renderer.backend.as_ref());
+        self.renderer.delete_programs();
+    }
+}
+
+impl GraphicsEngine {
+    /// Set up every renderer on `backend`, drawing to a `width` x `height` target. This is
+    /// how native code, such as tests on `SoftwareBackend`, gets an engine without a canvas;
+    /// the date starts at J2000.
+    pub fn with_backend(backend: Box<dyn GraphicsBackend>, width: u32, height: u32) -> Result<Self, String> {
+        backend.set_viewport(width, height);
+
+        let program = backend.create_program(ShaderKind::Basic)?;
+        let mut renderer = Renderer::new(backend, program);
+        // Create starfield shader program
+        let starfield_program = renderer.create_program(ShaderKind::Starfield)?;
+
+        // Upload shape meshes once; they are reused every frame
+        let triangle = Triangle::new(renderer.backend.as_ref(), &mut renderer.meshes)?;
+        let rectangle = Rectangle::new(renderer.backend.as_ref(), &mut renderer.meshes)?;
+        let mut solar_system_renderer = SolarSystemRenderer::new(&mut renderer)?;
+        let mut ring_renderer = RingRenderer::new(&mut renderer)?;
+        let mut orbit_renderer = OrbitRenderer::new(&mut renderer)?;
+        let trail_renderer = TrailRenderer::new(&mut renderer)?;
+        let constellation_renderer = ConstellationRenderer::new(&mut renderer)?;
+        let sky_renderer = SkyRenderer::new(&mut renderer)?;
+
+        // Create starfield with 5000 stars much further away than the planets
+        let mut starfield = Starfield::new(STARFIELD_SEED, 5000, SKY_RADIUS);
+        starfield.init_buffers(renderer.backend.as_ref())?;
+
+        let solar_system = SolarSystem::new();
+        solar_system_renderer.rebuild(&mut renderer, &solar_system.bodies)?;
+        ring_renderer.rebuild(&mut renderer, &solar_system.bodies)?;
+        orbit_renderer.rebuild(&mut renderer, &solar_system.bodies)?;
+
+        let mut camera = Camera::new();
+        camera.set_aspect_ratio(width as f32 / height.max(1) as f32);
+
+        Ok(GraphicsEngine {
+            renderer,
+            rotation: 0.0,
+            scale: 1.0,
+            color: [1.0, 1.0, 1.0],
+            translation: [0.0, 0.0],
+            background_color: [0.0, 0.0, 0.0, 1.0],
+            wireframe_mode: false,
+            camera,
+            solar_system,
+            starfield,
+            starfield_program,
+            triangle,
+            rectangle,
+            solar_system_renderer,
+            lighting: Lighting::new(),
+            ring_renderer,
+            orbit_renderer,
+            show_orbits: true,
+            trail_renderer,
+            show_trails: true,
+            constellation_renderer,
+            show_constellations: true,
+            show_constellation_boundaries: false,
+            sky_renderer,
+            show_milky_way: true,
+        })
+    }
+
+    fn set_body_texture(&mut self, index: usize, source: &TextureSource) -> Result<(), JsValue> {
+        if index >= self.solar_system.bodies.len() {
+            return Err(JsValue::from_str(&format!("No body at index {}", index)));
+        }
+        let texture = self.renderer.backend.create_texture(source)
+            .map_err(|e| JsValue::from_str(&e))?;
+        self.solar_system_renderer.set_diffuse_map(&mut self.renderer, index, Some(texture));
+        Ok(())
+    }
+    
+    fn load_system(&mut self, description: &SystemDescription) -> Result<(), JsValue> {
+        let bodies = description.build()?;
+        self.set_bodies(bodies).map_err(|e| JsValue::from_str(&e))?;
+        // Indices from the previous system no longer mean anything
+        self.camera.follow_target(None);
+        Ok(())
+    }
+
+    /// Replace the bodies along with everything the renderers upload for them. All of it is
+    /// uploaded before anything is swapped in, so on error the current system is untouched.
+    fn set_bodies(&mut self, bodies: Vec<CelestialBody>) -> Result<(), String> {
+        let renderer = &mut self.renderer;
+        let materials = SolarSystemRenderer::prepare(renderer, &bodies)?;
+        let rings = match RingRenderer::prepare(renderer, &bodies) {
+            Ok(rings) => rings,
+            Err(error) => {
+                SolarSystemRenderer::discard(renderer, materials);
+                return Err(error);
+            }
+        };
+        let orbits = match OrbitRenderer::prepare(renderer, &bodies) {
+            Ok(orbits) => orbits,
+            Err(error) => {
+                SolarSystemRenderer::discard(renderer, materials);
+                RingRenderer::discard(renderer, rings);
+                return Err(error);
+            }
+        };
+
+        self.solar_system_renderer.install(renderer, materials);
+        self.ring_renderer.install(renderer, rings);
+        self.orbit_renderer.install(renderer, orbits);
+        self.solar_system.set_bodies(bodies);
+        Ok(())
+    }
+}
+
+/// A WebGL 2 backend on `canvas`, or None when the browser has no WebGL 2. Every shader is
+/// first compiled on a scratch canvas: a canvas keeps the first kind of context it hands
+/// out, so `canvas` must not be claimed for WebGL 2 unless the engine will build on it.
+fn create_webgl2_backend(
+    document: &web_sys::Document,
+    canvas: &web_sys::HtmlCanvasElement,
+) -> Result<Option<WebGl2Backend>, String> {
+    let webgl2_context = |canvas: &web_sys::HtmlCanvasElement| {
+        canvas
+            .get_context("webgl2")
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<WebGl2RenderingContext>().ok())
+    };
+
+    let scratch = document
+        .create_element("canvas")
+        .ok()
+        .and_then(|element| element.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+        .ok_or("Failed to create a scratch canvas")?;
+    let Some(scratch_context) = webgl2_context(&scratch) else { return Ok(None) };
+    let probed = WebGl2Backend::new(scratch_context.clone()).and_then(|probe| {
+        ShaderKind::ALL.iter().try_for_each(|&shader| {
+            probe.create_program(shader).map(drop).map_err(|error| format!("{shader:?} shader: {error}"))
+        })
+    });
+
+    // Release the probe's context and everything on it now, rather than whenever the
+    // canvas is collected; browsers only keep a handful of live contexts
+    if let Some(extension) = scratch_context.get_extension("WEBGL_lose_context").ok().flatten() {
+        extension.unchecked_into::<web_sys::WebglLoseContext>().lose_context();
+    }
+    probed?;
+
+    let context = webgl2_context(canvas).ok_or("The canvas has no WebGL 2 context")?;
+    WebGl2Backend::new(context).map(Some)
+}
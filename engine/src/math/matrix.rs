@@ -0,0 +1,415 @@
+use std::ops::Mul;
+use super::quat::Quat;
+use super::vector::{Vec3, Vec4};
+
+/// 3x3 matrix stored as three columns, matching GL's column-major layout
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat3 {
+    pub cols: [Vec3; 3],
+}
+
+/// 4x4 matrix stored as four columns, matching GL's column-major layout
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4 {
+    pub cols: [Vec4; 4],
+}
+
+impl Default for Mat3 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mat3 {
+    pub const IDENTITY: Self = Self::from_cols(Vec3::X, Vec3::Y, Vec3::Z);
+
+    pub const fn from_cols(x: Vec3, y: Vec3, z: Vec3) -> Self {
+        Self { cols: [x, y, z] }
+    }
+
+    /// Upper-left 3x3 block of a 4x4 matrix (its rotation and scale part)
+    pub fn from_mat4(m: &Mat4) -> Self {
+        Self::from_cols(m.cols[0].truncate(), m.cols[1].truncate(), m.cols[2].truncate())
+    }
+
+    pub fn row(&self, index: usize) -> Vec3 {
+        let [x, y, z] = self.cols.map(|col| col.to_array()[index]);
+        Vec3::new(x, y, z)
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::from_cols(self.row(0), self.row(1), self.row(2))
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.cols[0].dot(self.cols[1].cross(self.cols[2]))
+    }
+
+    /// Returns `None` when the matrix is singular. The test is relative to the column
+    /// lengths, which bound the determinant, so small but well-formed scales still invert.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        let scale: f32 = self.cols.iter().map(|col| col.length()).product();
+        if det.abs() <= f32::EPSILON * scale || !det.is_finite() {
+            return None;
+        }
+        let [a, b, c] = self.cols;
+        // Rows of the inverse are the cross products of the columns
+        let inverse_transposed = Self::from_cols(b.cross(c), c.cross(a), a.cross(b));
+        Some(inverse_transposed.transpose() * (1.0 / det))
+    }
+
+    /// Flattened column-major array, ready for `uniform_matrix3fv`
+    pub fn to_cols_array(&self) -> [f32; 9] {
+        let [x, y, z] = self.cols;
+        [x.x, x.y, x.z, y.x, y.y, y.z, z.x, z.y, z.z]
+    }
+}
+
+impl Mat4 {
+    pub const IDENTITY: Self = Self::from_cols(
+        Vec4::new(1.0, 0.0, 0.0, 0.0),
+        Vec4::new(0.0, 1.0, 0.0, 0.0),
+        Vec4::new(0.0, 0.0, 1.0, 0.0),
+        Vec4::new(0.0, 0.0, 0.0, 1.0),
+    );
+
+    pub const fn from_cols(x: Vec4, y: Vec4, z: Vec4, w: Vec4) -> Self {
+        Self { cols: [x, y, z, w] }
+    }
+
+    /// Builds a matrix from a column-major array as used by WebGL
+    pub fn from_cols_array(m: &[f32; 16]) -> Self {
+        Self::from_cols(
+            Vec4::new(m[0], m[1], m[2], m[3]),
+            Vec4::new(m[4], m[5], m[6], m[7]),
+            Vec4::new(m[8], m[9], m[10], m[11]),
+            Vec4::new(m[12], m[13], m[14], m[15]),
+        )
+    }
+
+    /// Flattened column-major array, ready for `uniform_matrix4fv`
+    pub fn to_cols_array(&self) -> [f32; 16] {
+        let mut out = [0.0; 16];
+        for (i, col) in self.cols.iter().enumerate() {
+            out[i * 4..i * 4 + 4].copy_from_slice(&col.to_array());
+        }
+        out
+    }
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        let mut m = Self::IDENTITY;
+        m.cols[3] = translation.extend(1.0);
+        m
+    }
+
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self::from_cols(
+            Vec4::new(scale.x, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, scale.y, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, scale.z, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    pub fn from_rotation_x(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::from_cols(
+            Vec4::new(1.0, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, cos, sin, 0.0),
+            Vec4::new(0.0, -sin, cos, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    pub fn from_rotation_y(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::from_cols(
+            Vec4::new(cos, 0.0, -sin, 0.0),
+            Vec4::new(0.0, 1.0, 0.0, 0.0),
+            Vec4::new(sin, 0.0, cos, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    pub fn from_rotation_z(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::from_cols(
+            Vec4::new(cos, sin, 0.0, 0.0),
+            Vec4::new(-sin, cos, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, 1.0, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    pub fn from_quat(rotation: Quat) -> Self {
+        let Quat { x, y, z, w } = rotation;
+        let (x2, y2, z2) = (x + x, y + y, z + z);
+        let (xx, xy, xz) = (x * x2, x * y2, x * z2);
+        let (yy, yz, zz) = (y * y2, y * z2, z * z2);
+        let (wx, wy, wz) = (w * x2, w * y2, w * z2);
+
+        Self::from_cols(
+            Vec4::new(1.0 - (yy + zz), xy + wz, xz - wy, 0.0),
+            Vec4::new(xy - wz, 1.0 - (xx + zz), yz + wx, 0.0),
+            Vec4::new(xz + wy, yz - wx, 1.0 - (xx + yy), 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Model matrix applying scale, then rotation, then translation
+    pub fn from_scale_rotation_translation(scale: Vec3, rotation: Quat, translation: Vec3) -> Self {
+        Self::from_translation(translation) * Self::from_quat(rotation) * Self::from_scale(scale)
+    }
+
+    /// Right-handed view matrix looking from `eye` towards `target`
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        let forward = (target - eye).normalize();
+        let side = forward.cross(up).normalize();
+        let up = side.cross(forward);
+
+        Self::from_cols(
+            Vec4::new(side.x, up.x, -forward.x, 0.0),
+            Vec4::new(side.y, up.y, -forward.y, 0.0),
+            Vec4::new(side.z, up.z, -forward.z, 0.0),
+            Vec4::new(-side.dot(eye), -up.dot(eye), forward.dot(eye), 1.0),
+        )
+    }
+
+    /// GL perspective projection mapping depth into [-1, 1]; `fov` is vertical, in radians
+    pub fn perspective(fov: f32, aspect_ratio: f32, near: f32, far: f32) -> Self {
+        let f = 1.0 / (fov / 2.0).tan();
+        let range_inv = 1.0 / (near - far);
+
+        Self::from_cols(
+            Vec4::new(f / aspect_ratio, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, f, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, (near + far) * range_inv, -1.0),
+            Vec4::new(0.0, 0.0, 2.0 * near * far * range_inv, 0.0),
+        )
+    }
+
+    /// GL orthographic projection mapping the given box onto clip space
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        let width_inv = 1.0 / (right - left);
+        let height_inv = 1.0 / (top - bottom);
+        let depth_inv = 1.0 / (far - near);
+
+        Self::from_cols(
+            Vec4::new(2.0 * width_inv, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 2.0 * height_inv, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, -2.0 * depth_inv, 0.0),
+            Vec4::new(
+                -(right + left) * width_inv,
+                -(top + bottom) * height_inv,
+                -(far + near) * depth_inv,
+                1.0,
+            ),
+        )
+    }
+
+    pub fn row(&self, index: usize) -> Vec4 {
+        let [x, y, z, w] = self.cols.map(|col| col.to_array()[index]);
+        Vec4::new(x, y, z, w)
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::from_cols(self.row(0), self.row(1), self.row(2), self.row(3))
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = self.to_cols_array();
+        let cofactors = Self::cofactors(&m);
+        m[0] * cofactors[0] + m[1] * cofactors[4] + m[2] * cofactors[8] + m[3] * cofactors[12]
+    }
+
+    /// Returns `None` when the matrix is singular, judged relative to the column lengths
+    /// as for `Mat3::inverse`
+    pub fn inverse(&self) -> Option<Self> {
+        let m = self.to_cols_array();
+        let cofactors = Self::cofactors(&m);
+        let det = m[0] * cofactors[0] + m[1] * cofactors[4] + m[2] * cofactors[8] + m[3] * cofactors[12];
+        let scale: f32 = self.cols.iter().map(|col| col.length()).product();
+        if det.abs() <= f32::EPSILON * scale || !det.is_finite() {
+            return None;
+        }
+        Some(Self::from_cols_array(&cofactors.map(|c| c / det)))
+    }
+
+    /// Adjugate of a column-major matrix, also column-major
+    fn cofactors(m: &[f32; 16]) -> [f32; 16] {
+        let mut inv = [0.0; 16];
+
+        inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+            + m[9] * m[7] * m[14] + m[13] * m[6] * m[11] - m[13] * m[7] * m[10];
+        inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+            - m[8] * m[7] * m[14] - m[12] * m[6] * m[11] + m[12] * m[7] * m[10];
+        inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+            + m[8] * m[7] * m[13] + m[12] * m[5] * m[11] - m[12] * m[7] * m[9];
+        inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+            - m[8] * m[6] * m[13] - m[12] * m[5] * m[10] + m[12] * m[6] * m[9];
+        inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+            - m[9] * m[3] * m[14] - m[13] * m[2] * m[11] + m[13] * m[3] * m[10];
+        inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+            + m[8] * m[3] * m[14] + m[12] * m[2] * m[11] - m[12] * m[3] * m[10];
+        inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+            - m[8] * m[3] * m[13] - m[12] * m[1] * m[11] + m[12] * m[3] * m[9];
+        inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+            + m[8] * m[2] * m[13] + m[12] * m[1] * m[10] - m[12] * m[2] * m[9];
+        inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+            + m[5] * m[3] * m[14] + m[13] * m[2] * m[7] - m[13] * m[3] * m[6];
+        inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+            - m[4] * m[3] * m[14] - m[12] * m[2] * m[7] + m[12] * m[3] * m[6];
+        inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+            + m[4] * m[3] * m[13] + m[12] * m[1] * m[7] - m[12] * m[3] * m[5];
+        inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+            - m[4] * m[2] * m[13] - m[12] * m[1] * m[6] + m[12] * m[2] * m[5];
+        inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+            - m[5] * m[3] * m[10] - m[9] * m[2] * m[7] + m[9] * m[3] * m[6];
+        inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+            + m[4] * m[3] * m[10] + m[8] * m[2] * m[7] - m[8] * m[3] * m[6];
+        inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+            - m[4] * m[3] * m[9] - m[8] * m[1] * m[7] + m[8] * m[3] * m[5];
+        inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+            + m[4] * m[2] * m[9] + m[8] * m[1] * m[6] - m[8] * m[2] * m[5];
+
+        inv
+    }
+
+    /// Inverse-transpose of the upper 3x3 block, for transforming normals
+    /// under non-uniform scale
+    pub fn normal_matrix(&self) -> Mat3 {
+        Mat3::from_mat4(self)
+            .inverse()
+            .map(|inverse| inverse.transpose())
+            .unwrap_or(Mat3::IDENTITY)
+    }
+
+    /// Transforms a point (w = 1), including translation and perspective divide
+    pub fn transform_point3(&self, point: Vec3) -> Vec3 {
+        let result = *self * point.extend(1.0);
+        if result.w != 0.0 && result.w != 1.0 {
+            result.perspective_divide()
+        } else {
+            result.truncate()
+        }
+    }
+
+    /// Transforms a direction (w = 0), ignoring translation
+    pub fn transform_vector3(&self, vector: Vec3) -> Vec3 {
+        (*self * vector.extend(0.0)).truncate()
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self::from_cols(self * rhs.cols[0], self * rhs.cols[1], self * rhs.cols[2])
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+    fn mul(self, rhs: Vec3) -> Vec3 {
+        self.cols[0] * rhs.x + self.cols[1] * rhs.y + self.cols[2] * rhs.z
+    }
+}
+
+impl Mul<f32> for Mat3 {
+    type Output = Self;
+    fn mul(self, rhs: f32) -> Self {
+        Self { cols: self.cols.map(|col| col * rhs) }
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self { cols: rhs.cols.map(|col| self * col) }
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+    fn mul(self, rhs: Vec4) -> Vec4 {
+        self.cols[0] * rhs.x + self.cols[1] * rhs.y + self.cols[2] * rhs.z + self.cols[3] * rhs.w
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &[f32], b: &[f32]) {
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-4, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn a_matrix_times_its_inverse_is_identity() {
+        let m = Mat4::from_scale_rotation_translation(
+            Vec3::new(2.0, 0.5, 3.0),
+            Quat::from_axis_angle(Vec3::new(1.0, 2.0, -1.0).normalize(), 0.7),
+            Vec3::new(-4.0, 1.0, 9.0),
+        );
+        let inverse = m.inverse().unwrap();
+        assert_close(&(m * inverse).to_cols_array(), &Mat4::IDENTITY.to_cols_array());
+        assert_close(&(inverse * m).to_cols_array(), &Mat4::IDENTITY.to_cols_array());
+        assert!(Mat4::from_scale(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn look_at_puts_the_eye_at_the_origin_looking_down_negative_z() {
+        let eye = Vec3::new(3.0, 4.0, 5.0);
+        let target = Vec3::new(-1.0, 0.0, 2.0);
+        let view = Mat4::look_at(eye, target, Vec3::Y);
+
+        assert_close(&view.transform_point3(eye).to_array(), &[0.0, 0.0, 0.0]);
+        let distance = (target - eye).length();
+        assert_close(&view.transform_point3(target).to_array(), &[0.0, 0.0, -distance]);
+    }
+
+    #[test]
+    fn perspective_maps_near_and_far_to_the_ends_of_clip_depth() {
+        let projection = Mat4::perspective(1.0, 1.5, 0.1, 100.0);
+        let depth = |distance: f32| (projection * Vec4::new(0.0, 0.0, -distance, 1.0)).perspective_divide().z;
+        assert!((depth(0.1) + 1.0).abs() < 1e-4);
+        assert!((depth(100.0) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn quaternion_rotations_match_matrix_rotations() {
+        let angle = 1.1;
+        let point = Vec3::new(0.3, -2.0, 5.0);
+        let pairs = [
+            (Quat::from_rotation_x(angle), Mat4::from_rotation_x(angle)),
+            (Quat::from_rotation_y(angle), Mat4::from_rotation_y(angle)),
+            (Quat::from_rotation_z(angle), Mat4::from_rotation_z(angle)),
+        ];
+        for (quat, matrix) in pairs {
+            assert_close(&(quat * point).to_array(), &matrix.transform_point3(point).to_array());
+            assert_close(&Mat4::from_quat(quat).to_cols_array(), &matrix.to_cols_array());
+        }
+    }
+
+    #[test]
+    fn tiny_bodies_keep_their_rotation_in_the_normal_matrix() {
+        let rotation = Quat::from_axis_angle(Vec3::new(0.3, 1.0, 0.2).normalize(), 0.9);
+        let model = Mat4::from_scale_rotation_translation(Vec3::splat(0.004), rotation, Vec3::new(5.0, 0.0, -2.0));
+
+        let inverse = model.inverse().expect("a 0.004-scaled model is invertible");
+        assert_close(&(model * inverse).to_cols_array(), &Mat4::IDENTITY.to_cols_array());
+
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let transformed = (model.normal_matrix() * normal).normalize();
+        assert_close(&transformed.to_array(), &(rotation * normal).to_array());
+        assert!(Mat3::from_cols(Vec3::X, Vec3::Y, Vec3::ZERO).inverse().is_none());
+    }
+}
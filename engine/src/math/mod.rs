@@ -0,0 +1,14 @@
+pub mod vector;
+pub mod matrix;
+pub mod quat;
+
+pub use vector::{Vec2, Vec3, Vec4};
+pub use matrix::{Mat3, Mat4};
+pub use quat::Quat;
+
+pub fn create_rotation_matrix_2d(rotation: f32, scale: f32, translation: [f32; 2]) -> [f32; 16] {
+    let translation = Vec3::new(translation[0], translation[1], 0.0);
+    let scale = Vec3::new(scale, scale, 1.0);
+
+    Mat4::from_scale_rotation_translation(scale, Quat::from_rotation_z(rotation), translation).to_cols_array()
+}
@@ -0,0 +1,145 @@
+//! Deterministic gradient noise for procedural textures. Everything here is plain
+//! Rust, so the same seed gives the same values on every platform.
+
+use crate::math::Vec3;
+use crate::random::Rng;
+
+/// Ken Perlin's improved 3D gradient noise with a seeded permutation table
+#[derive(Clone)]
+pub struct Perlin {
+    permutation: [u8; 512], // Doubled so lookups never need to wrap
+}
+
+impl Perlin {
+    pub fn new(seed: u32) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        Rng::new(seed as u64).shuffle(&mut table);
+
+        let mut permutation = [0; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = table[i & 255];
+        }
+        Self { permutation }
+    }
+
+    /// Noise at `point`, roughly in -1..1 and exactly 0 on integer lattice points
+    pub fn noise(&self, point: Vec3) -> f32 {
+        let cell = [point.x.floor(), point.y.floor(), point.z.floor()];
+        let [x, y, z] = [point.x - cell[0], point.y - cell[1], point.z - cell[2]];
+        let [xi, yi, zi] = cell.map(|c| (c as i32 & 255) as usize);
+
+        let p = &self.permutation;
+        let a = p[xi] as usize + yi;
+        let aa = p[a] as usize + zi;
+        let ab = p[a + 1] as usize + zi;
+        let b = p[xi + 1] as usize + yi;
+        let ba = p[b] as usize + zi;
+        let bb = p[b + 1] as usize + zi;
+
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, gradient(p[aa], x, y, z), gradient(p[ba], x - 1.0, y, z)),
+                lerp(u, gradient(p[ab], x, y - 1.0, z), gradient(p[bb], x - 1.0, y - 1.0, z)),
+            ),
+            lerp(
+                v,
+                lerp(u, gradient(p[aa + 1], x, y, z - 1.0), gradient(p[ba + 1], x - 1.0, y, z - 1.0)),
+                lerp(u, gradient(p[ab + 1], x, y - 1.0, z - 1.0), gradient(p[bb + 1], x - 1.0, y - 1.0, z - 1.0)),
+            ),
+        )
+    }
+
+    /// Fractal Brownian motion: `octaves` layers of noise, each at twice the frequency
+    /// and `gain` times the amplitude of the last. Normalised back to roughly -1..1.
+    pub fn fbm(&self, point: Vec3, octaves: u32, gain: f32) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut frequency = 1.0;
+
+        for octave in 0..octaves {
+            // Offset each octave so their lattices do not line up at the origin
+            let offset = Vec3::splat(octave as f32 * 17.31);
+            sum += amplitude * self.noise(point * frequency + offset);
+            total += amplitude;
+            amplitude *= gain;
+            frequency *= 2.0;
+        }
+
+        if total > 0.0 { sum / total } else { 0.0 }
+    }
+
+    /// fBm of `1 - |noise|`, which turns zero crossings into sharp ridges. In 0..1.
+    pub fn ridged(&self, point: Vec3, octaves: u32, gain: f32) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut frequency = 1.0;
+
+        for octave in 0..octaves {
+            let offset = Vec3::splat(octave as f32 * 17.31);
+            let ridge = 1.0 - self.noise(point * frequency + offset).abs();
+            sum += amplitude * ridge * ridge;
+            total += amplitude;
+            amplitude *= gain;
+            frequency *= 2.0;
+        }
+
+        if total > 0.0 { sum / total } else { 0.0 }
+    }
+}
+
+/// 6t^5 - 15t^4 + 10t^3, which has zero first and second derivatives at 0 and 1
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// Dot product with one of 12 cube-edge gradients picked by `hash`
+fn gradient(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 { y } else if h == 12 || h == 14 { x } else { z };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_points() -> impl Iterator<Item = Vec3> {
+        let mut rng = Rng::new(3);
+        (0..2000).map(move |_| Vec3::new(rng.range(-50.0, 50.0), rng.range(-50.0, 50.0), rng.range(-50.0, 50.0)))
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_noise() {
+        let (a, b, other) = (Perlin::new(11), Perlin::new(11), Perlin::new(12));
+        let mut differs = false;
+        for point in sample_points() {
+            assert_eq!(a.noise(point), b.noise(point));
+            assert_eq!(a.fbm(point, 5, 0.5), b.fbm(point, 5, 0.5));
+            assert_eq!(a.ridged(point, 5, 0.5), b.ridged(point, 5, 0.5));
+            differs |= a.noise(point) != other.noise(point);
+        }
+        assert!(differs);
+    }
+
+    #[test]
+    fn noise_stays_in_its_documented_range() {
+        let perlin = Perlin::new(5);
+        for point in sample_points() {
+            // "Roughly -1..1": improved noise can overshoot by a few percent
+            assert!(perlin.noise(point).abs() <= 1.1);
+            assert!(perlin.fbm(point, 6, 0.5).abs() <= 1.1);
+            assert!((0.0..=1.0).contains(&perlin.ridged(point, 6, 0.5)));
+        }
+        assert_eq!(perlin.noise(Vec3::new(3.0, -7.0, 12.0)), 0.0);
+    }
+}
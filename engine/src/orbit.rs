@@ -0,0 +1,163 @@
+use std::f32::consts::PI;
+use crate::math::Vec3;
+
+/// Classical Keplerian elements describing an orbit around a parent body.
+///
+/// Positions are computed in the ecliptic frame and mapped into engine space
+/// with +Y as ecliptic north: ecliptic (x, y, z) becomes engine (x, z, -y).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrbitalElements {
+    pub semi_major_axis: f32,
+    pub eccentricity: f32,                 // 0 = circle, < 1 = ellipse
+    pub inclination: f32,                  // Radians from the reference plane
+    pub longitude_of_ascending_node: f32,  // Radians
+    pub argument_of_periapsis: f32,        // Radians, measured from the ascending node
+    pub mean_anomaly_at_epoch: f32,        // Radians
+}
+
+impl OrbitalElements {
+    /// Angles are given in degrees, matching how published element tables list them
+    pub fn from_degrees(
+        semi_major_axis: f32,
+        eccentricity: f32,
+        inclination: f32,
+        longitude_of_ascending_node: f32,
+        argument_of_periapsis: f32,
+        mean_anomaly_at_epoch: f32,
+    ) -> Self {
+        Self {
+            semi_major_axis,
+            eccentricity,
+            inclination: inclination.to_radians(),
+            longitude_of_ascending_node: longitude_of_ascending_node.to_radians(),
+            argument_of_periapsis: argument_of_periapsis.to_radians(),
+            mean_anomaly_at_epoch: mean_anomaly_at_epoch.to_radians(),
+        }
+    }
+
+    /// Circular orbit in the reference plane
+    pub fn circular(radius: f32) -> Self {
+        Self::from_degrees(radius, 0.0, 0.0, 0.0, 0.0, 0.0)
+    }
+
+    /// Mean anomaly `days_since_epoch` days after the elements' epoch, for an orbit taking
+    /// `period` days. Computed in f64 so long time spans do not lose precision.
+    pub fn mean_anomaly_at(&self, days_since_epoch: f64, period: f64) -> f32 {
+        if period == 0.0 {
+            return self.mean_anomaly_at_epoch;
+        }
+        let tau = std::f64::consts::TAU;
+        (self.mean_anomaly_at_epoch as f64 + tau * days_since_epoch / period).rem_euclid(tau) as f32
+    }
+
+    /// Position relative to the parent for the given mean anomaly, in engine space
+    pub fn position_at_mean_anomaly(&self, mean_anomaly: f32) -> Vec3 {
+        self.position_at_eccentric_anomaly(solve_kepler(mean_anomaly, self.eccentricity))
+    }
+
+    /// Centre and semi-axes of the ellipse, relative to the parent: the point at eccentric
+    /// anomaly `E` is `center + major_axis * cos E + minor_axis * sin E`
+    pub fn ellipse(&self) -> (Vec3, Vec3, Vec3) {
+        let e = self.eccentricity;
+        let a = self.semi_major_axis;
+        let b = a * (1.0 - e * e).sqrt();
+        (self.in_engine_space(-a * e, 0.0), self.in_engine_space(a, 0.0), self.in_engine_space(0.0, b))
+    }
+
+    fn position_at_eccentric_anomaly(&self, eccentric_anomaly: f32) -> Vec3 {
+        let e = self.eccentricity;
+        let a = self.semi_major_axis;
+
+        // Position in the orbital plane, periapsis along +x
+        let x = a * (eccentric_anomaly.cos() - e);
+        let y = a * (1.0 - e * e).sqrt() * eccentric_anomaly.sin();
+        self.in_engine_space(x, y)
+    }
+
+    /// Map a point in the orbital plane, periapsis along +x, into engine space
+    fn in_engine_space(&self, x: f32, y: f32) -> Vec3 {
+        let (sin_w, cos_w) = self.argument_of_periapsis.sin_cos();
+        let (sin_o, cos_o) = self.longitude_of_ascending_node.sin_cos();
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+
+        // Rotate by periapsis, inclination and node into the ecliptic frame
+        let xw = x * cos_w - y * sin_w;
+        let yw = x * sin_w + y * cos_w;
+        let ecliptic_x = xw * cos_o - yw * cos_i * sin_o;
+        let ecliptic_y = xw * sin_o + yw * cos_i * cos_o;
+        let ecliptic_z = yw * sin_i;
+
+        Vec3::new(ecliptic_x, ecliptic_z, -ecliptic_y)
+    }
+}
+
+/// Solve Kepler's equation `M = E - e sin E` for the eccentric anomaly `E`
+pub fn solve_kepler(mean_anomaly: f32, eccentricity: f32) -> f32 {
+    let m = (mean_anomaly + PI).rem_euclid(2.0 * PI) - PI;
+    let mut e_anomaly = if eccentricity < 0.8 { m } else { PI.copysign(m) };
+
+    // Newton-Raphson converges in a handful of steps for elliptical orbits
+    for _ in 0..16 {
+        let delta = (e_anomaly - eccentricity * e_anomaly.sin() - m) / (1.0 - eccentricity * e_anomaly.cos());
+        e_anomaly -= delta;
+        if delta.abs() < 1e-6 {
+            break;
+        }
+    }
+
+    e_anomaly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn circular_orbits_have_eccentric_equal_to_mean_anomaly() {
+        for m in [-3.0, -1.0, 0.0, 0.5, 2.5, 3.1] {
+            assert!((solve_kepler(m, 0.0) - m).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn kepler_residual_is_small_for_eccentric_orbits() {
+        let mean_anomalies = [-PI, -PI + 1e-3, -2.0, -0.3, 0.0, 1e-3, 1.0, 2.9, PI - 1e-3, PI];
+        for e in [0.5, 0.9, 0.99] {
+            for m in mean_anomalies {
+                let eccentric = solve_kepler(m, e);
+                let residual = (eccentric - e * eccentric.sin() - m + PI).rem_euclid(2.0 * PI) - PI;
+                assert!(residual.abs() < 1e-5, "e = {}, M = {}: residual {}", e, m, residual);
+            }
+        }
+    }
+
+    #[test]
+    fn periapsis_and_apoapsis_are_at_the_right_distances() {
+        let orbit = OrbitalElements::from_degrees(2.0, 0.3, 12.0, 40.0, 75.0, 0.0);
+        assert!((orbit.position_at_mean_anomaly(0.0).length() - 2.0 * (1.0 - 0.3)).abs() < 1e-5);
+        assert!((orbit.position_at_mean_anomaly(PI).length() - 2.0 * (1.0 + 0.3)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ellipse_matches_positions_along_the_orbit() {
+        let orbit = OrbitalElements::from_degrees(1.5, 0.4, 20.0, 110.0, 30.0, 0.0);
+        let (center, major_axis, minor_axis) = orbit.ellipse();
+
+        // E = 0 at M = 0, and E = pi/2 at M = pi/2 - e
+        assert_close(orbit.position_at_mean_anomaly(0.0), center + major_axis);
+        assert_close(orbit.position_at_mean_anomaly(FRAC_PI_2 - 0.4), center + minor_axis);
+    }
+
+    #[test]
+    fn a_polar_orbit_rises_along_engine_y() {
+        let orbit = OrbitalElements::from_degrees(1.0, 0.0, 90.0, 0.0, 0.0, 0.0);
+        assert_close(orbit.position_at_mean_anomaly(0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_close(orbit.position_at_mean_anomaly(FRAC_PI_2), Vec3::new(0.0, 1.0, 0.0));
+        assert_close(orbit.position_at_mean_anomaly(-FRAC_PI_2), Vec3::new(0.0, -1.0, 0.0));
+    }
+}
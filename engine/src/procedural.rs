@@ -0,0 +1,107 @@
+//! Procedural surface textures, so bodies look like planets without shipping image assets.
+
+use std::f32::consts::PI;
+use serde::Deserialize;
+use crate::math::Vec3;
+use crate::noise::Perlin;
+
+pub const SURFACE_TEXTURE_WIDTH: u32 = 256;
+pub const SURFACE_TEXTURE_HEIGHT: u32 = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SurfaceKind {
+    Rocky,    // Mottled terrain with ridges
+    GasGiant, // Latitude bands with turbulent edges
+    Icy,      // Smooth, bright plains criss-crossed by cracks
+}
+
+/// How to generate a body's surface map
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Surface {
+    pub kind: SurfaceKind,
+    pub seed: u32,
+    pub palette: [[f32; 3]; 3], // Colors for low, middle and high noise values
+}
+
+impl Surface {
+    /// Dark, base and light shades of `color`, for bodies that only specify one color
+    pub fn palette_from_color(color: [f32; 3]) -> [[f32; 3]; 3] {
+        let color = Vec3::from(color);
+        [color * 0.55, color, color.lerp(Vec3::ONE, 0.35)].map(|c| c.to_array())
+    }
+
+    /// Equirectangular RGBA pixels, top row first, matching the sphere's UVs. Noise is
+    /// sampled on the unit sphere, so there is no seam at u = 0 and no pinching at the poles.
+    pub fn generate(&self, width: u32, height: u32) -> Vec<u8> {
+        let perlin = Perlin::new(self.seed);
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+
+        for row in 0..height {
+            let theta = (row as f32 + 0.5) / height as f32 * PI;
+            for column in 0..width {
+                let phi = (column as f32 + 0.5) / width as f32 * 2.0 * PI;
+                let direction = Vec3::new(theta.sin() * phi.cos(), theta.cos(), -theta.sin() * phi.sin());
+
+                let value = match self.kind {
+                    SurfaceKind::Rocky => rocky(&perlin, direction),
+                    SurfaceKind::GasGiant => gas_giant(&perlin, direction),
+                    SurfaceKind::Icy => icy(&perlin, direction),
+                };
+                let color = sample_palette(&self.palette, value);
+                pixels.extend(color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+                pixels.push(255);
+            }
+        }
+
+        pixels
+    }
+}
+
+/// Continents of fBm with ridged mountains on top
+fn rocky(perlin: &Perlin, direction: Vec3) -> f32 {
+    // fBm rarely strays far from zero, so stretch it to use the whole palette
+    let terrain = perlin.fbm(direction * 2.5, 5, 0.5) * 1.6;
+    let mountains = perlin.ridged(direction * 6.0, 3, 0.5) - 0.5;
+    (0.5 + terrain + mountains * 0.3).clamp(0.0, 1.0)
+}
+
+/// Bands by latitude, pushed around by low-frequency turbulence
+fn gas_giant(perlin: &Perlin, direction: Vec3) -> f32 {
+    let turbulence = perlin.fbm(direction * 3.0, 5, 0.55);
+    let latitude = direction.y + turbulence * 0.08;
+    let bands = (latitude * 14.0).sin() * 0.5 + 0.5;
+    let fine = (latitude * 41.0).sin() * 0.15;
+    (bands * 0.85 + fine + 0.05).clamp(0.0, 1.0)
+}
+
+/// Bright ice with dark lineae where ridged noise peaks
+fn icy(perlin: &Perlin, direction: Vec3) -> f32 {
+    let plains = perlin.fbm(direction * 2.0, 4, 0.5) * 0.15 + 0.85;
+    let cracks = perlin.ridged(direction * 4.0, 3, 0.6).powf(6.0);
+    (plains - cracks * 0.7).clamp(0.0, 1.0)
+}
+
+/// Piecewise-linear gradient through the three palette stops
+fn sample_palette(palette: &[[f32; 3]; 3], t: f32) -> [f32; 3] {
+    let t = t.clamp(0.0, 1.0) * 2.0;
+    let (from, to, amount) = if t < 1.0 { (palette[0], palette[1], t) } else { (palette[1], palette[2], t - 1.0) };
+    Vec3::from(from).lerp(Vec3::from(to), amount).to_array()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generators_fill_the_whole_texture_deterministically() {
+        for kind in [SurfaceKind::Rocky, SurfaceKind::GasGiant, SurfaceKind::Icy] {
+            let surface = |seed| Surface { kind, seed, palette: Surface::palette_from_color([0.4, 0.6, 0.8]) };
+            let pixels = surface(9).generate(64, 32);
+            assert_eq!(pixels.len(), 64 * 32 * 4);
+            assert!(pixels.chunks_exact(4).all(|pixel| pixel[3] == 255));
+            assert_eq!(pixels, surface(9).generate(64, 32));
+            assert_ne!(pixels, surface(10).generate(64, 32));
+        }
+    }
+}
@@ -1,27 +1,45 @@
-use web_sys::{WebGlProgram, WebGlRenderingContext};
+use crate::backend::{GraphicsBackend, ProgramHandle, ShaderKind};
+use crate::shapes::MeshCache;
 
 pub struct Renderer {
-    pub context: WebGlRenderingContext,
-    pub program: WebGlProgram,
+    pub backend: Box<dyn GraphicsBackend>,
+    pub program: ProgramHandle,
+    pub meshes: MeshCache,
+    programs: Vec<ProgramHandle>, // Every program created through `create_program`, plus `program`
 }
 
 impl Renderer {
-    pub fn new(context: WebGlRenderingContext, program: WebGlProgram) -> Self {
-        Self { context, program }
+    pub fn new(backend: Box<dyn GraphicsBackend>, program: ProgramHandle) -> Self {
+        Self {
+            backend,
+            program,
+            meshes: MeshCache::new(),
+            programs: vec![program],
+        }
+    }
+
+    /// Compile `shader` on the backend, remembering it for `delete_programs`
+    pub fn create_program(&mut self, shader: ShaderKind) -> Result<ProgramHandle, String> {
+        let program = self.backend.create_program(shader)?;
+        self.programs.push(program);
+        Ok(program)
+    }
+
+    /// Delete every program this renderer knows of; nothing can be drawn afterwards
+    pub fn delete_programs(&mut self) {
+        for program in self.programs.drain(..) {
+            self.backend.delete_program(program);
+        }
     }
 
     pub fn clear(&self, background_color: [f32; 4]) {
-        self.context.clear_color(background_color[0], background_color[1], background_color[2], background_color[3]);
-        self.context.clear(WebGlRenderingContext::COLOR_BUFFER_BIT);
+        self.backend.clear(background_color, false);
     }
 
     pub fn clear_3d(&self, background_color: [f32; 4]) {
-        self.context.clear_color(background_color[0], background_color[1], background_color[2], background_color[3]);
-        self.context.enable(WebGlRenderingContext::DEPTH_TEST);
-        self.context.depth_func(WebGlRenderingContext::LESS);
-        self.context.disable(WebGlRenderingContext::CULL_FACE); // Show both front and back faces
-        self.context.clear(WebGlRenderingContext::COLOR_BUFFER_BIT | WebGlRenderingContext::DEPTH_BUFFER_BIT);
+        self.backend.set_depth_test(true);
+        self.backend.clear(background_color, true);
     }
 
     // Shape-specific render methods removed - GraphicsEngine now calls shapes directly
-}
\ No newline at end of file
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use crate::camera::Camera;
+use crate::catalog::equatorial_to_engine;
+use crate::constellations::{ConstellationBoundary, ConstellationFigure};
+use crate::starfield::Starfield;
+use crate::shapes::geometry::Geometry;
+use crate::shapes::mesh::MeshHandle;
+use crate::math::{Mat4, Vec3};
+use crate::renderer::Renderer;
+use crate::backend::{ProgramHandle, Uniform};
+use crate::shaders::ShaderKind;
+
+/// Boundary edges are split so no piece spans more than this, keeping arcs of constant
+/// declination curved on the sky
+const BOUNDARY_STEP: f32 = PI / 180.0;
+
+/// Two vertices per segment, so this stays under the 65536 vertices a u16 index can reach
+const SEGMENTS_PER_MESH: usize = 30_000;
+
+/// Draws constellation stick figures and boundaries on the starfield's sphere
+pub struct ConstellationRenderer {
+    program: ProgramHandle,
+    figures: Vec<ConstellationFigure>,
+    figure_meshes: Vec<MeshHandle>,
+    boundary_meshes: Vec<MeshHandle>,
+    pub figure_color: [f32; 4],
+    pub boundary_color: [f32; 4],
+}
+
+impl ConstellationRenderer {
+    pub fn new(renderer: &mut Renderer) -> Result<Self, String> {
+        let program = renderer.create_program(ShaderKind::Line)?;
+        Ok(Self {
+            program,
+            figures: Vec::new(),
+            figure_meshes: Vec::new(),
+            boundary_meshes: Vec::new(),
+            figure_color: [0.35, 0.55, 0.9, 0.45],
+            boundary_color: [0.6, 0.45, 0.3, 0.25],
+        })
+    }
+
+    /// Replace the stick figures and connect them using the stars in `starfield`
+    pub fn set_figures(&mut self, renderer: &mut Renderer, figures: Vec<ConstellationFigure>, starfield: &Starfield) -> Result<(), String> {
+        self.figures = figures;
+        self.rebuild_figures(renderer, starfield)
+    }
+
+    /// Re-upload the stick figures; call whenever the starfield changes. Segments whose
+    /// stars are not in the catalog, or that have no Hipparcos numbers, are skipped.
+    pub fn rebuild_figures(&mut self, renderer: &mut Renderer, starfield: &Starfield) -> Result<(), String> {
+        free_meshes(renderer, &mut self.figure_meshes);
+
+        let positions: HashMap<u32, Vec3> = starfield
+            .stars()
+            .iter()
+            .filter_map(|star| star.hip.map(|hip| (hip, Vec3::from(star.position))))
+            .collect();
+        let segments: Vec<(Vec3, Vec3)> = self
+            .figures
+            .iter()
+            .flat_map(|figure| &figure.segments)
+            .filter_map(|(a, b)| Some((*positions.get(a)?, *positions.get(b)?)))
+            .collect();
+
+        self.figure_meshes = upload_segments(renderer, &segments)?;
+        Ok(())
+    }
+
+    /// Replace the boundary outlines, drawn on a sphere of `radius`
+    pub fn set_boundaries(&mut self, renderer: &mut Renderer, boundaries: &[ConstellationBoundary], radius: f32) -> Result<(), String> {
+        free_meshes(renderer, &mut self.boundary_meshes);
+
+        let mut segments = Vec::new();
+        for boundary in boundaries {
+            let vertices = &boundary.vertices;
+            for (i, &(ra, dec)) in vertices.iter().enumerate() {
+                let (next_ra, next_dec) = vertices[(i + 1) % vertices.len()];
+
+                // Go the short way round when an edge crosses 0h
+                let delta_ra = (next_ra - ra + PI).rem_euclid(2.0 * PI) - PI;
+                let delta_dec = next_dec - dec;
+                let span = (delta_ra * dec.cos().max(next_dec.cos())).abs().max(delta_dec.abs());
+                let steps = ((span / BOUNDARY_STEP).ceil() as usize).max(1);
+
+                let point = |t: f32| equatorial_to_engine(ra + delta_ra * t, dec + delta_dec * t) * radius;
+                for step in 0..steps {
+                    segments.push((point(step as f32 / steps as f32), point((step + 1) as f32 / steps as f32)));
+                }
+            }
+        }
+
+        self.boundary_meshes = upload_segments(renderer, &segments)?;
+        Ok(())
+    }
+
+    pub fn free(&mut self, renderer: &mut Renderer) {
+        free_meshes(renderer, &mut self.figure_meshes);
+        free_meshes(renderer, &mut self.boundary_meshes);
+    }
+
+    /// Draw the requested overlays with blending on and depth writes off, after the starfield
+    pub fn render(&self, camera: &Camera, renderer: &Renderer, show_figures: bool, show_boundaries: bool) {
+        let backend = renderer.backend.as_ref();
+        let uniform = |name: &str, value: Uniform| backend.set_uniform(self.program, name, value);
+        uniform("u_model_matrix", Uniform::Mat4(Mat4::IDENTITY.to_cols_array()));
+        uniform("u_view_matrix", Uniform::Mat4(camera.view_matrix().to_cols_array()));
+        uniform("u_projection_matrix", Uniform::Mat4(camera.projection_matrix().to_cols_array()));
+
+        backend.set_depth_write(false);
+
+        for (visible, meshes, color) in [
+            (show_boundaries, &self.boundary_meshes, &self.boundary_color),
+            (show_figures, &self.figure_meshes, &self.figure_color),
+        ] {
+            if !visible {
+                continue;
+            }
+            uniform("u_color", Uniform::Vec4(*color));
+            for mesh in meshes.iter().filter_map(|handle| renderer.meshes.get(*handle)) {
+                mesh.draw(backend, self.program, false);
+            }
+        }
+
+        backend.set_depth_write(true);
+    }
+}
+
+fn upload_segments(renderer: &mut Renderer, segments: &[(Vec3, Vec3)]) -> Result<Vec<MeshHandle>, String> {
+    segments
+        .chunks(SEGMENTS_PER_MESH)
+        .map(|chunk| {
+            let positions = chunk.iter().flat_map(|(a, b)| a.to_array().into_iter().chain(b.to_array())).collect();
+            renderer.meshes.upload(renderer.backend.as_ref(), &Geometry::lines(positions))
+        })
+        .collect()
+}
+
+fn free_meshes(renderer: &mut Renderer, meshes: &mut Vec<MeshHandle>) {
+    for mesh in meshes.drain(..) {
+        renderer.meshes.free(renderer.backend.as_ref(), mesh);
+    }
+}
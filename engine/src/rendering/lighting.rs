@@ -0,0 +1,42 @@
+use crate::backend::{GraphicsBackend, ProgramHandle, Uniform};
+use crate::math::Vec3;
+
+/// Point light settings; the light itself sits at the Sun's position
+pub struct Lighting {
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub ambient: f32,   // Fraction of the surface color visible on the night side
+    pub specular: f32,  // Strength of the highlight
+    pub shininess: f32, // Blinn-Phong exponent; higher is a tighter highlight
+}
+
+impl Lighting {
+    pub fn new() -> Self {
+        Self {
+            color: [1.0, 0.97, 0.9],
+            intensity: 1.0,
+            ambient: 0.08,
+            specular: 0.25,
+            shininess: 24.0,
+        }
+    }
+
+    /// Upload the light uniforms used by `LIT_FRAGMENT_SHADER`
+    pub fn apply(
+        &self,
+        backend: &dyn GraphicsBackend,
+        program: ProgramHandle,
+        light_position: Vec3,
+        camera_position: Vec3,
+    ) {
+        let uniform = |name: &str, value: Uniform| backend.set_uniform(program, name, value);
+
+        uniform("u_light_position", Uniform::Vec3(light_position.to_array()));
+        uniform("u_light_color", Uniform::Vec3(self.color));
+        uniform("u_light_intensity", Uniform::Float(self.intensity));
+        uniform("u_ambient", Uniform::Float(self.ambient));
+        uniform("u_specular", Uniform::Float(self.specular));
+        uniform("u_shininess", Uniform::Float(self.shininess));
+        uniform("u_camera_position", Uniform::Vec3(camera_position.to_array()));
+    }
+}
@@ -0,0 +1,24 @@
+use crate::backend::{GraphicsBackend, ProgramHandle, TextureHandle, Uniform};
+
+/// Surface maps for a body; anything missing falls back to the body's flat color
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Material {
+    pub diffuse_map: Option<TextureHandle>,
+}
+
+impl Material {
+    /// Point the program's samplers at the maps and flag which ones are present
+    pub fn apply(&self, backend: &dyn GraphicsBackend, program: ProgramHandle) {
+        if let Some(texture) = self.diffuse_map {
+            backend.set_uniform(program, "u_diffuse_map", Uniform::Texture(texture));
+        }
+        backend.set_uniform(program, "u_has_diffuse_map", Uniform::Bool(self.diffuse_map.is_some()));
+    }
+
+    /// Free every texture the material owns
+    pub fn free(&mut self, backend: &dyn GraphicsBackend) {
+        if let Some(handle) = self.diffuse_map.take() {
+            backend.delete_texture(handle);
+        }
+    }
+}
@@ -1,3 +1,16 @@
+pub mod constellation_renderer;
+pub mod lighting;
+pub mod material;
+pub mod orbit_renderer;
+pub mod ring_renderer;
+pub mod sky_renderer;
 pub mod solar_system_renderer;
+pub mod trail_renderer;
 
-pub use solar_system_renderer::SolarSystemRenderer;
\ No newline at end of file
+pub use constellation_renderer::ConstellationRenderer;
+pub use lighting::Lighting;
+pub use orbit_renderer::OrbitRenderer;
+pub use ring_renderer::RingRenderer;
+pub use sky_renderer::SkyRenderer;
+pub use solar_system_renderer::SolarSystemRenderer;
+pub use trail_renderer::TrailRenderer;
@@ -0,0 +1,181 @@
+use std::f32::consts::TAU;
+use crate::camera::Camera;
+use crate::solar_system::{CelestialBody, SolarSystem};
+use crate::shapes::geometry::Geometry;
+use crate::shapes::mesh::{Mesh, MeshHandle};
+use crate::math::{Mat4, Vec3, Vec4};
+use crate::backend::{BufferData, BufferHandle, BufferUsage, ProgramHandle, Uniform, VertexAttribute};
+use crate::renderer::Renderer;
+use crate::shaders::ShaderKind;
+
+const ORBIT_SEGMENTS: u32 = 256;
+const FLOATS_PER_INSTANCE: usize = 13; // Center, major axis, minor axis, RGBA
+
+/// Draws every body's ideal orbit around its parent in one instanced call: a single unit
+/// circle, stretched onto each orbit's ellipse in the vertex shader. Backends without
+/// instancing get one draw per orbit instead, with the ellipse in the model matrix.
+pub struct OrbitRenderer {
+    program: ProgramHandle,
+    line_program: ProgramHandle, // For backends without instancing
+    circle: Option<MeshHandle>,
+    instance_buffer: Option<BufferHandle>,
+    ellipses: Vec<Option<(Vec3, Vec3, Vec3)>>, // One per body, relative to the parent; None for bodies without an orbit
+}
+
+/// The orbit renderer's per-system resources, uploaded by `OrbitRenderer::prepare`
+pub struct OrbitShapes {
+    circle: MeshHandle,
+    instance_buffer: BufferHandle,
+    ellipses: Vec<Option<(Vec3, Vec3, Vec3)>>,
+}
+
+impl OrbitRenderer {
+    pub fn new(renderer: &mut Renderer) -> Result<Self, String> {
+        let program = renderer.create_program(ShaderKind::Orbit)?;
+        let line_program = renderer.create_program(ShaderKind::Line)?;
+        Ok(Self { program, line_program, circle: None, instance_buffer: None, ellipses: Vec::new() })
+    }
+
+    /// Re-upload the orbit shapes; call whenever the set of bodies changes
+    pub fn rebuild(&mut self, renderer: &mut Renderer, bodies: &[CelestialBody]) -> Result<(), String> {
+        let shapes = Self::prepare(renderer, bodies)?;
+        self.install(renderer, shapes);
+        Ok(())
+    }
+
+    /// Upload the orbit shapes for `bodies` without touching the current ones; on error
+    /// nothing is left allocated
+    pub fn prepare(renderer: &mut Renderer, bodies: &[CelestialBody]) -> Result<OrbitShapes, String> {
+        let backend = renderer.backend.as_ref();
+
+        // Points evenly spaced in eccentric anomaly keep the curve smooth near periapsis
+        let positions = (0..ORBIT_SEGMENTS)
+            .flat_map(|i| {
+                let angle = i as f32 * TAU / ORBIT_SEGMENTS as f32;
+                [angle.cos(), angle.sin(), 0.0]
+            })
+            .collect();
+        let circle = renderer.meshes.upload(backend, &Geometry::line_loop(positions))?;
+
+        let ellipses: Vec<_> = bodies
+            .iter()
+            .map(|body| (!body.is_sun && body.orbit.semi_major_axis > 0.0).then(|| body.orbit.ellipse()))
+            .collect();
+
+        let instances = vec![0.0; ellipses.len().max(1) * FLOATS_PER_INSTANCE];
+        let instance_buffer = match backend.create_buffer(BufferData::Vertices(&instances), BufferUsage::Dynamic) {
+            Ok(buffer) => buffer,
+            Err(error) => {
+                renderer.meshes.free(backend, circle);
+                return Err(error);
+            }
+        };
+
+        Ok(OrbitShapes { circle, instance_buffer, ellipses })
+    }
+
+    /// Swap in shapes from `prepare`, freeing the current ones
+    pub fn install(&mut self, renderer: &mut Renderer, shapes: OrbitShapes) {
+        self.free(renderer);
+        self.circle = Some(shapes.circle);
+        self.instance_buffer = Some(shapes.instance_buffer);
+        self.ellipses = shapes.ellipses;
+    }
+
+    pub fn free(&mut self, renderer: &mut Renderer) {
+        if let Some(circle) = self.circle.take() {
+            renderer.meshes.free(renderer.backend.as_ref(), circle);
+        }
+        if let Some(buffer) = self.instance_buffer.take() {
+            renderer.backend.delete_buffer(buffer);
+        }
+        self.ellipses.clear();
+    }
+
+    /// Draw visible orbits with blending on and depth writes off, so call after opaque geometry
+    pub fn render(&self, solar_system: &SolarSystem, camera: &Camera, renderer: &Renderer) {
+        let (Some(circle), Some(instance_buffer)) = (self.circle, self.instance_buffer) else { return };
+        let Some(mesh) = renderer.meshes.get(circle) else { return };
+        let backend = renderer.backend.as_ref();
+
+        let mut instances = Vec::with_capacity(self.ellipses.len() * FLOATS_PER_INSTANCE);
+        for (index, (body, ellipse)) in solar_system.bodies.iter().zip(&self.ellipses).enumerate() {
+            let (Some((center, major_axis, minor_axis)), true) = (ellipse, body.show_orbit) else { continue };
+
+            // The followed body's orbit is brightened and drawn fully opaque
+            let [r, g, b, a] = body.orbit_color;
+            let color = if camera.followed_target == Some(index) {
+                let highlight = Vec3::new(r, g, b).lerp(Vec3::ONE, 0.4);
+                [highlight.x, highlight.y, highlight.z, 1.0]
+            } else {
+                [r, g, b, a]
+            };
+
+            let center = *center + Vec3::from(body.orbit_center(&solar_system.bodies));
+            instances.extend(center.to_array());
+            instances.extend(major_axis.to_array());
+            instances.extend(minor_axis.to_array());
+            instances.extend(color);
+        }
+        if instances.is_empty() {
+            return;
+        }
+
+        backend.set_depth_write(false);
+        if backend.supports_instancing() {
+            self.draw_instanced(renderer, mesh, instance_buffer, &instances, camera);
+        } else {
+            self.draw_each(renderer, mesh, &instances, camera);
+        }
+        backend.set_depth_write(true);
+    }
+
+    fn draw_instanced(&self, renderer: &Renderer, mesh: &Mesh, instance_buffer: BufferHandle, instances: &[f32], camera: &Camera) {
+        let backend = renderer.backend.as_ref();
+        backend.update_buffer(instance_buffer, BufferData::Vertices(instances));
+        set_camera(renderer, self.program, camera);
+
+        let stride = (FLOATS_PER_INSTANCE * 4) as i32;
+        let instance_attribute = |name, size, offset| VertexAttribute {
+            name,
+            buffer: instance_buffer,
+            size,
+            stride,
+            offset: offset * 4,
+            divisor: 1,
+        };
+        let attributes = [
+            instance_attribute("i_center", 3, 0),
+            instance_attribute("i_major_axis", 3, 3),
+            instance_attribute("i_minor_axis", 3, 6),
+            instance_attribute("i_color", 4, 9),
+        ];
+        mesh.draw_instanced(backend, self.program, false, &attributes, instances.len() / FLOATS_PER_INSTANCE);
+    }
+
+    /// One `Line` draw per orbit, with the instance data turned into uniforms
+    fn draw_each(&self, renderer: &Renderer, mesh: &Mesh, instances: &[f32], camera: &Camera) {
+        let backend = renderer.backend.as_ref();
+        set_camera(renderer, self.line_program, camera);
+
+        let vec3 = |floats: &[f32]| Vec3::new(floats[0], floats[1], floats[2]);
+        for instance in instances.chunks_exact(FLOATS_PER_INSTANCE) {
+            // Maps the unit circle's (cos E, sin E) onto the ellipse, as the Orbit shader does
+            let model = Mat4::from_cols(
+                vec3(&instance[3..6]).extend(0.0),
+                vec3(&instance[6..9]).extend(0.0),
+                Vec4::ZERO,
+                vec3(&instance[0..3]).extend(1.0),
+            );
+            backend.set_uniform(self.line_program, "u_model_matrix", Uniform::Mat4(model.to_cols_array()));
+            backend.set_uniform(self.line_program, "u_color", Uniform::Vec4([instance[9], instance[10], instance[11], instance[12]]));
+            mesh.draw(backend, self.line_program, false);
+        }
+    }
+}
+
+fn set_camera(renderer: &Renderer, program: ProgramHandle, camera: &Camera) {
+    let backend = renderer.backend.as_ref();
+    backend.set_uniform(program, "u_view_matrix", Uniform::Mat4(camera.view_matrix().to_cols_array()));
+    backend.set_uniform(program, "u_projection_matrix", Uniform::Mat4(camera.projection_matrix().to_cols_array()));
+}
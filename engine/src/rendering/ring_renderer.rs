@@ -0,0 +1,109 @@
+use crate::camera::Camera;
+use crate::solar_system::{CelestialBody, SolarSystem};
+use crate::shapes::{Ring, RenderableShape};
+use crate::math::{Mat4, Vec3};
+use crate::backend::{ProgramHandle, Uniform};
+use crate::renderer::Renderer;
+use crate::shaders::ShaderKind;
+use super::lighting::Lighting;
+
+const RING_SEGMENTS: u32 = 128;
+
+/// Draws planetary rings as translucent annuli in each body's equatorial plane
+pub struct RingRenderer {
+    program: ProgramHandle,
+    rings: Vec<Option<Ring>>, // One per body; None for bodies without rings
+}
+
+impl RingRenderer {
+    pub fn new(renderer: &mut Renderer) -> Result<Self, String> {
+        let program = renderer.create_program(ShaderKind::Ring)?;
+        Ok(Self { program, rings: Vec::new() })
+    }
+
+    /// Re-upload the ring meshes; call whenever the set of bodies changes
+    pub fn rebuild(&mut self, renderer: &mut Renderer, bodies: &[CelestialBody]) -> Result<(), String> {
+        let rings = Self::prepare(renderer, bodies)?;
+        self.install(renderer, rings);
+        Ok(())
+    }
+
+    /// Upload ring meshes for `bodies` without touching the current ones; on error the
+    /// meshes made so far are freed
+    pub fn prepare(renderer: &mut Renderer, bodies: &[CelestialBody]) -> Result<Vec<Option<Ring>>, String> {
+        let mut rings = Vec::with_capacity(bodies.len());
+        for body in bodies {
+            let ring = match body.rings {
+                Some(body_rings) => {
+                    let backend = renderer.backend.as_ref();
+                    match Ring::new(backend, &mut renderer.meshes, body_rings.inner_radius, body_rings.outer_radius, RING_SEGMENTS) {
+                        Ok(ring) => Some(ring),
+                        Err(error) => {
+                            Self::discard(renderer, rings);
+                            return Err(error);
+                        }
+                    }
+                }
+                None => None,
+            };
+            rings.push(ring);
+        }
+        Ok(rings)
+    }
+
+    /// Swap in meshes from `prepare`, freeing the current ones
+    pub fn install(&mut self, renderer: &mut Renderer, rings: Vec<Option<Ring>>) {
+        self.free(renderer);
+        self.rings = rings;
+    }
+
+    /// Free meshes from `prepare` that will not be installed
+    pub fn discard(renderer: &mut Renderer, rings: Vec<Option<Ring>>) {
+        for ring in rings.into_iter().flatten() {
+            ring.free(renderer.backend.as_ref(), &mut renderer.meshes);
+        }
+    }
+
+    pub fn free(&mut self, renderer: &mut Renderer) {
+        Self::discard(renderer, std::mem::take(&mut self.rings));
+    }
+
+    /// Draw rings with blending on and depth writes off, so call after opaque geometry
+    pub fn render(&self, solar_system: &SolarSystem, camera: &Camera, renderer: &Renderer, lighting: &Lighting, wireframe_mode: bool) {
+        let backend = renderer.backend.as_ref();
+        let uniform = |name: &str, value: Uniform| backend.set_uniform(self.program, name, value);
+        uniform("u_view_matrix", Uniform::Mat4(camera.view_matrix().to_cols_array()));
+        uniform("u_projection_matrix", Uniform::Mat4(camera.projection_matrix().to_cols_array()));
+
+        let light_position = solar_system
+            .sun()
+            .map(|sun| Vec3::from(sun.get_position()))
+            .unwrap_or(Vec3::ZERO);
+        lighting.apply(backend, self.program, light_position, camera.eye_position());
+
+        backend.set_depth_write(false);
+
+        let days = solar_system.clock.days_since_j2000();
+        for (body, ring) in solar_system.bodies.iter().zip(&self.rings) {
+            let (Some(ring), Some(rings)) = (ring, body.rings) else { continue };
+
+            // Same orientation as the body, so the rings follow its equator
+            let model = Mat4::from_scale_rotation_translation(
+                Vec3::splat(body.radius),
+                body.orientation(days),
+                Vec3::from(body.get_position()),
+            );
+
+            uniform("u_model_matrix", Uniform::Mat4(model.to_cols_array()));
+            uniform("u_color", Uniform::Vec3(rings.color));
+            uniform("u_opacity", Uniform::Float(rings.opacity));
+            uniform("u_shadow", Uniform::Bool(rings.receives_shadow));
+            uniform("u_planet_center", Uniform::Vec3(body.get_position()));
+            uniform("u_planet_radius", Uniform::Float(body.radius));
+
+            ring.draw(backend, self.program, &renderer.meshes, wireframe_mode);
+        }
+
+        backend.set_depth_write(true);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::camera::Camera;
+use crate::catalog::engine_to_galactic;
+use crate::shapes::{Sphere, RenderableShape};
+use crate::math::{Mat4, Vec3, Vec4};
+use crate::renderer::Renderer;
+use crate::backend::{ProgramHandle, Uniform};
+use crate::shaders::ShaderKind;
+
+/// Draws the Milky Way and bright nebulae on a dome around the camera, behind everything
+pub struct SkyRenderer {
+    dome: Sphere,
+    program: ProgramHandle,
+    pub intensity: f32,    // 0 hides the band; 1 is a dark-site sky
+    pub show_nebulae: bool,
+}
+
+impl SkyRenderer {
+    pub fn new(renderer: &mut Renderer) -> Result<Self, String> {
+        // Only directions matter, so a coarse unit sphere is plenty
+        let dome = Sphere::new(renderer.backend.as_ref(), &mut renderer.meshes, 1.0, 24, 48)?;
+        let program = renderer.create_program(ShaderKind::Sky)?;
+        Ok(Self { dome, program, intensity: 0.35, show_nebulae: true })
+    }
+
+    pub fn free(&self, renderer: &mut Renderer) {
+        self.dome.free(renderer.backend.as_ref(), &mut renderer.meshes);
+    }
+
+    /// Draw with blending on, straight after clearing; it neither tests nor writes depth
+    pub fn render(&self, camera: &Camera, renderer: &Renderer) {
+        if self.intensity <= 0.0 {
+            return;
+        }
+
+        let backend = renderer.backend.as_ref();
+
+        // Keep the view's rotation only, so the dome never moves relative to the camera
+        let mut view_rotation = camera.view_matrix();
+        view_rotation.cols[3] = Vec4::new(0.0, 0.0, 0.0, 1.0);
+        // Scale the unit dome to halfway between the clip planes so none of it is clipped
+        let dome_scale = Mat4::from_scale(Vec3::splat((camera.near + camera.far) * 0.5));
+
+        let uniform = |name: &str, value: Uniform| backend.set_uniform(self.program, name, value);
+        uniform("u_view_rotation", Uniform::Mat4((view_rotation * dome_scale).to_cols_array()));
+        uniform("u_projection_matrix", Uniform::Mat4(camera.projection_matrix().to_cols_array()));
+        uniform("u_to_galactic", Uniform::Mat3(engine_to_galactic().to_cols_array()));
+        uniform("u_intensity", Uniform::Float(self.intensity));
+        uniform("u_nebulae", Uniform::Bool(self.show_nebulae));
+
+        backend.set_depth_test(false);
+        backend.set_depth_write(false);
+        self.dome.draw(backend, self.program, &renderer.meshes, false);
+        backend.set_depth_write(true);
+        backend.set_depth_test(true);
+    }
+}
@@ -1,47 +1,134 @@
 use crate::camera::Camera;
-use crate::solar_system::SolarSystem;
+use crate::solar_system::{CelestialBody, SolarSystem};
 use crate::shapes::{Sphere, RenderableShape};
-use crate::math::create_aspect_corrected_matrix;
+use crate::math::{Mat4, Vec3};
 use crate::renderer::Renderer;
+use crate::shaders::ShaderKind;
+use crate::procedural::{SURFACE_TEXTURE_WIDTH, SURFACE_TEXTURE_HEIGHT};
+use crate::texture::TextureSource;
+use crate::backend::{ProgramHandle, TextureHandle, Uniform};
+use super::lighting::Lighting;
+use super::material::Material;
 
-pub struct SolarSystemRenderer;
+pub struct SolarSystemRenderer {
+    sphere: Sphere,
+    program: ProgramHandle,
+    materials: Vec<Material>, // One per body
+}
 
 impl SolarSystemRenderer {
+    pub fn new(renderer: &mut Renderer) -> Result<Self, String> {
+        // Unit sphere shared by every body, scaled by its model matrix
+        let sphere = Sphere::new(renderer.backend.as_ref(), &mut renderer.meshes, 1.0, 32, 32)?;
+        let program = renderer.create_program(ShaderKind::Lit)?;
+        Ok(Self { sphere, program, materials: Vec::new() })
+    }
+
+    /// Drop every body's maps and generate fresh ones from their procedural surfaces;
+    /// call whenever the set of bodies changes
+    pub fn rebuild(&mut self, renderer: &mut Renderer, bodies: &[CelestialBody]) -> Result<(), String> {
+        let materials = Self::prepare(renderer, bodies)?;
+        self.install(renderer, materials);
+        Ok(())
+    }
+
+    /// Generate maps for `bodies` without touching the current ones; on error the maps made
+    /// so far are freed
+    pub fn prepare(renderer: &mut Renderer, bodies: &[CelestialBody]) -> Result<Vec<Material>, String> {
+        let mut materials = Vec::with_capacity(bodies.len());
+        for body in bodies {
+            let diffuse_map = match &body.surface {
+                Some(surface) => {
+                    let pixels = surface.generate(SURFACE_TEXTURE_WIDTH, SURFACE_TEXTURE_HEIGHT);
+                    let source = TextureSource::Rgba { width: SURFACE_TEXTURE_WIDTH, height: SURFACE_TEXTURE_HEIGHT, pixels: &pixels };
+                    match renderer.backend.create_texture(&source) {
+                        Ok(texture) => Some(texture),
+                        Err(error) => {
+                            Self::discard(renderer, materials);
+                            return Err(error);
+                        }
+                    }
+                }
+                None => None,
+            };
+            materials.push(Material { diffuse_map });
+        }
+        Ok(materials)
+    }
+
+    /// Swap in maps from `prepare`, freeing the current ones
+    pub fn install(&mut self, renderer: &mut Renderer, materials: Vec<Material>) {
+        self.free_materials(renderer);
+        self.materials = materials;
+    }
+
+    /// Free maps from `prepare` that will not be installed
+    pub fn discard(renderer: &mut Renderer, materials: Vec<Material>) {
+        for mut material in materials {
+            material.free(renderer.backend.as_ref());
+        }
+    }
+
+    /// Use `texture` as body `index`'s surface map, freeing the one it replaces
+    pub fn set_diffuse_map(&mut self, renderer: &mut Renderer, index: usize, texture: Option<TextureHandle>) {
+        let Some(material) = self.materials.get_mut(index) else {
+            if let Some(texture) = texture {
+                renderer.backend.delete_texture(texture);
+            }
+            return;
+        };
+        if let Some(old) = material.diffuse_map {
+            renderer.backend.delete_texture(old);
+        }
+        material.diffuse_map = texture;
+    }
+
+    pub fn free(&mut self, renderer: &mut Renderer) {
+        self.sphere.free(renderer.backend.as_ref(), &mut renderer.meshes);
+        self.free_materials(renderer);
+    }
+
+    fn free_materials(&mut self, renderer: &mut Renderer) {
+        Self::discard(renderer, std::mem::take(&mut self.materials));
+    }
+
     pub fn render(
+        &self,
         solar_system: &SolarSystem,
         camera: &Camera,
         renderer: &Renderer,
+        lighting: &Lighting,
         wireframe_mode: bool,
     ) {
-        // Use the camera's interpolated center position
-        let center_position = camera.get_current_center();
-        
+        let backend = renderer.backend.as_ref();
+        let uniform = |name: &str, value: Uniform| backend.set_uniform(self.program, name, value);
+
+        // Shared by every body, and by the starfield, so everything lines up in 3D
+        uniform("u_view_matrix", Uniform::Mat4(camera.view_matrix().to_cols_array()));
+        uniform("u_projection_matrix", Uniform::Mat4(camera.projection_matrix().to_cols_array()));
+
+        let light_position = solar_system
+            .sun()
+            .map(|sun| Vec3::from(sun.get_position()))
+            .unwrap_or(Vec3::ZERO);
+        lighting.apply(backend, self.program, light_position, camera.eye_position());
+
         // Render each celestial body
-        for body in &solar_system.bodies {
-            let position = body.get_position();
-            
-            // Transform position through camera
-            let (screen_pos, z_depth) = camera.transform_point(position, center_position);
-            
-            // Use z for depth-based scaling (simple perspective)
-            let depth_factor = 1.0 / (1.0 + z_depth * 0.1).max(0.1);
-            let scale_factor = 1.0 / camera.distance;
-            
-            // Don't apply aspect ratio to radius - handle it in the matrix
-            let final_radius = body.radius * scale_factor * depth_factor;
-            
-            // Create and render sphere
-            let sphere = Sphere::new(final_radius, 16, 16);
-            let matrix = create_aspect_corrected_matrix(0.0, 1.0, screen_pos, camera.aspect_ratio);
-            
-            sphere.render(
-                &renderer.context,
-                &renderer.program,
-                [screen_pos[0], screen_pos[1], 0.0],
-                body.color,
-                &matrix,
-                wireframe_mode,
+        let days = solar_system.clock.days_since_j2000();
+        for (index, body) in solar_system.bodies.iter().enumerate() {
+            let model = Mat4::from_scale_rotation_translation(
+                Vec3::splat(body.radius),
+                body.orientation(days),
+                Vec3::from(body.get_position()),
             );
+
+            uniform("u_model_matrix", Uniform::Mat4(model.to_cols_array()));
+            uniform("u_normal_matrix", Uniform::Mat3(model.normal_matrix().to_cols_array()));
+            uniform("u_color", Uniform::Vec3(body.color));
+            uniform("u_emissive", Uniform::Bool(body.is_sun));
+            self.materials.get(index).copied().unwrap_or_default().apply(backend, self.program);
+
+            self.sphere.draw(backend, self.program, &renderer.meshes, wireframe_mode);
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,96 @@
+use crate::camera::Camera;
+use crate::solar_system::SolarSystem;
+use crate::math::Vec3;
+use crate::renderer::Renderer;
+use crate::backend::{BufferData, BufferHandle, BufferUsage, DrawCall, ProgramHandle, Uniform, VertexAttribute};
+use crate::shapes::geometry::Primitive;
+use crate::shaders::ShaderKind;
+
+const FLOATS_PER_VERTEX: usize = 4; // x, y, z, freshness
+
+/// Draws each body's recent motion as a line strip that fades with age
+pub struct TrailRenderer {
+    program: ProgramHandle,
+    vertex_buffer: BufferHandle, // Refilled every frame, since trails change every frame
+}
+
+impl TrailRenderer {
+    pub fn new(renderer: &mut Renderer) -> Result<Self, String> {
+        let program = renderer.create_program(ShaderKind::Trail)?;
+        let vertex_buffer = renderer.backend.create_buffer(BufferData::Vertices(&[]), BufferUsage::Dynamic)?;
+        Ok(Self { program, vertex_buffer })
+    }
+
+    pub fn free(&self, renderer: &Renderer) {
+        renderer.backend.delete_buffer(self.vertex_buffer);
+    }
+
+    /// Draw every trail with blending on and depth writes off, so call after opaque geometry.
+    ///
+    /// While the camera follows a body, trails are drawn in that body's frame: each sample is
+    /// offset by where the followed body was at the time, which shows apparent motion such as
+    /// the retrograde loops of Mars seen from Earth.
+    pub fn render(&self, solar_system: &SolarSystem, camera: &Camera, renderer: &Renderer) {
+        let backend = renderer.backend.as_ref();
+        let now = solar_system.clock.days_since_j2000();
+        let frame = camera.followed_target.filter(|&index| index < solar_system.bodies.len());
+        let frame_now = frame.map(|index| solar_system.world_position_at(index, now));
+
+        // One buffer for all trails; each body draws its own range
+        let mut vertices = Vec::new();
+        let mut ranges = Vec::new();
+        for (index, body) in solar_system.bodies.iter().enumerate() {
+            if body.trail_length <= 0.0 || frame == Some(index) {
+                continue;
+            }
+
+            let first = vertices.len() / FLOATS_PER_VERTEX;
+            let current = (now, Vec3::from(body.get_position()));
+            for (days, position) in body.trail.samples().map(|s| (s.days, s.position)).chain([current]) {
+                let position = match (frame, frame_now) {
+                    (Some(frame), Some(frame_now)) => position - solar_system.world_position_at(frame, days) + frame_now,
+                    _ => position,
+                };
+                let freshness = 1.0 - ((now - days).abs() / body.trail_length).min(1.0) as f32;
+                vertices.extend_from_slice(&[position.x, position.y, position.z, freshness]);
+            }
+
+            let count = vertices.len() / FLOATS_PER_VERTEX - first;
+            if count >= 2 {
+                ranges.push((index, first, count));
+            }
+        }
+
+        if ranges.is_empty() {
+            return;
+        }
+
+        backend.update_buffer(self.vertex_buffer, BufferData::Vertices(&vertices));
+
+        let stride = (FLOATS_PER_VERTEX * 4) as i32;
+        let attributes = [
+            VertexAttribute { name: "position", buffer: self.vertex_buffer, size: 3, stride, offset: 0, divisor: 0 },
+            VertexAttribute { name: "freshness", buffer: self.vertex_buffer, size: 1, stride, offset: 3 * 4, divisor: 0 },
+        ];
+
+        let uniform = |name: &str, value: Uniform| backend.set_uniform(self.program, name, value);
+        uniform("u_view_matrix", Uniform::Mat4(camera.view_matrix().to_cols_array()));
+        uniform("u_projection_matrix", Uniform::Mat4(camera.projection_matrix().to_cols_array()));
+
+        backend.set_depth_write(false);
+        for (index, first, count) in ranges {
+            let [r, g, b] = solar_system.bodies[index].color;
+            uniform("u_color", Uniform::Vec4([r, g, b, 0.9]));
+            backend.draw(&DrawCall {
+                program: self.program,
+                attributes: &attributes,
+                indices: None,
+                primitive: Primitive::LineStrip,
+                first,
+                count,
+                instances: 1,
+            });
+        }
+        backend.set_depth_write(true);
+    }
+}
@@ -0,0 +1,457 @@
+//! Planetary system descriptions loaded from JSON or TOML.
+//!
+//! ```toml
+//! [[bodies]]
+//! name = "Kepler-16"
+//! radius = 0.12
+//! color = [1.0, 0.8, 0.5]
+//! is_sun = true
+//!
+//! [[bodies]]
+//! name = "Kepler-16b"
+//! radius = 0.05
+//! axial_tilt = 12.0
+//! rotation_period = 1.3
+//! orbit = { semi_major_axis = 1.4, eccentricity = 0.007, period = 228.8 }
+//! rings = { inner_radius = 1.3, outer_radius = 2.1, opacity = 0.4 }
+//! surface = { kind = "rocky", seed = 7 }
+//! ```
+//!
+//! Angles are in degrees, periods in days and ring radii in body radii; orbital elements are taken to be
+//! valid at J2000. Parents are referenced by name.
+
+use std::collections::{HashMap, HashSet};
+use serde::Deserialize;
+use wasm_bindgen::JsValue;
+use crate::orbit::OrbitalElements;
+use crate::procedural::{Surface, SurfaceKind};
+use crate::solar_system::{CelestialBody, Rings};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SystemDescription {
+    pub bodies: Vec<BodyDescription>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BodyDescription {
+    pub name: String,
+    pub radius: f32,
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub orbit: Option<OrbitDescription>,
+    #[serde(default = "default_color")]
+    pub color: [f32; 3],
+    #[serde(default)]
+    pub is_sun: bool,
+    #[serde(default)]
+    pub orbit_color: Option<[f32; 4]>, // Defaults to the body color, mostly transparent
+    #[serde(default)]
+    pub trail_length: Option<f64>,     // Days; defaults to half an orbit, at most a year
+    #[serde(default)]
+    pub axial_tilt: f32,
+    #[serde(default)]
+    pub rotation_period: f64,          // Sidereal days; 0 does not spin
+    #[serde(default)]
+    pub rotation_phase: f32,
+    #[serde(default)]
+    pub rings: Option<RingsDescription>,
+    #[serde(default)]
+    pub surface: Option<SurfaceDescription>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OrbitDescription {
+    pub semi_major_axis: f32,
+    #[serde(default)]
+    pub eccentricity: f32,
+    #[serde(default)]
+    pub inclination: f32,
+    #[serde(default)]
+    pub longitude_of_ascending_node: f32,
+    #[serde(default)]
+    pub argument_of_periapsis: f32,
+    #[serde(default)]
+    pub mean_anomaly_at_epoch: f32,
+    pub period: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RingsDescription {
+    pub inner_radius: f32,       // In units of the body's radius
+    pub outer_radius: f32,
+    #[serde(default)]
+    pub color: Option<[f32; 3]>, // Defaults to the body color
+    #[serde(default = "default_ring_opacity")]
+    pub opacity: f32,
+    #[serde(default = "default_true")]
+    pub receives_shadow: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SurfaceDescription {
+    pub kind: SurfaceKind,                // "rocky", "gas_giant" or "icy"
+    #[serde(default)]
+    pub seed: u32,
+    #[serde(default)]
+    pub palette: Option<[[f32; 3]; 3]>,   // Low, middle and high colors; defaults to shades of the body color
+}
+
+fn default_color() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_ring_opacity() -> f32 {
+    0.6
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// One problem found while validating a description
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    pub path: String, // e.g. "bodies[2].orbit.eccentricity"
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SceneError {
+    /// The text is not valid JSON/TOML or does not match the expected shape
+    Parse(String),
+    /// The description parsed but describes an impossible system
+    Invalid(Vec<ValidationIssue>),
+}
+
+impl From<SceneError> for JsValue {
+    /// `{ kind: "parse", message }` or `{ kind: "validation", message, issues: [{ path, message }] }`
+    fn from(error: SceneError) -> Self {
+        let object = js_sys::Object::new();
+        let set = |target: &js_sys::Object, key: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(target, &JsValue::from_str(key), &value);
+        };
+
+        match error {
+            SceneError::Parse(message) => {
+                set(&object, "kind", "parse".into());
+                set(&object, "message", message.into());
+            }
+            SceneError::Invalid(issues) => {
+                set(&object, "kind", "validation".into());
+                set(&object, "message", format!("{} validation error(s)", issues.len()).into());
+                let list = js_sys::Array::new();
+                for issue in issues {
+                    let entry = js_sys::Object::new();
+                    set(&entry, "path", issue.path.into());
+                    set(&entry, "message", issue.message.into());
+                    list.push(&entry);
+                }
+                set(&object, "issues", list.into());
+            }
+        }
+
+        object.into()
+    }
+}
+
+impl SystemDescription {
+    pub fn from_json(source: &str) -> Result<Self, SceneError> {
+        serde_json::from_str(source).map_err(|e| SceneError::Parse(e.to_string()))
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, SceneError> {
+        toml::from_str(source).map_err(|e| SceneError::Parse(e.to_string()))
+    }
+
+    /// Validate the description and turn it into bodies for `SolarSystem::set_bodies`
+    pub fn build(&self) -> Result<Vec<CelestialBody>, SceneError> {
+        let issues = self.validate();
+        if !issues.is_empty() {
+            return Err(SceneError::Invalid(issues));
+        }
+
+        let indices: HashMap<&str, usize> = self
+            .bodies
+            .iter()
+            .enumerate()
+            .map(|(index, body)| (body.name.as_str(), index))
+            .collect();
+
+        Ok(self
+            .bodies
+            .iter()
+            .map(|body| {
+                let (orbit, period) = match &body.orbit {
+                    Some(orbit) => (
+                        OrbitalElements::from_degrees(
+                            orbit.semi_major_axis,
+                            orbit.eccentricity,
+                            orbit.inclination,
+                            orbit.longitude_of_ascending_node,
+                            orbit.argument_of_periapsis,
+                            orbit.mean_anomaly_at_epoch,
+                        ),
+                        orbit.period,
+                    ),
+                    None => (OrbitalElements::circular(0.0), 0.0),
+                };
+
+                let mut celestial = CelestialBody::new(&body.name, body.radius, orbit, period, body.color, body.is_sun)
+                    .spinning(body.axial_tilt, body.rotation_period, body.rotation_phase);
+                if let Some(parent) = &body.parent {
+                    celestial = celestial.orbiting(indices[parent.as_str()]);
+                }
+                if let Some(orbit_color) = body.orbit_color {
+                    celestial.orbit_color = orbit_color;
+                }
+                if let Some(rings) = &body.rings {
+                    celestial = celestial.with_rings(Rings {
+                        inner_radius: rings.inner_radius,
+                        outer_radius: rings.outer_radius,
+                        color: rings.color.unwrap_or(body.color),
+                        opacity: rings.opacity,
+                        receives_shadow: rings.receives_shadow,
+                    });
+                }
+                if let Some(surface) = &body.surface {
+                    celestial.surface = Some(Surface {
+                        kind: surface.kind,
+                        seed: surface.seed,
+                        palette: surface.palette.unwrap_or_else(|| Surface::palette_from_color(body.color)),
+                    });
+                }
+                if let Some(trail_length) = body.trail_length {
+                    celestial.trail_length = trail_length;
+                }
+                celestial
+            })
+            .collect())
+    }
+
+    fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let mut issue = |path: String, message: &str| {
+            issues.push(ValidationIssue { path, message: message.to_string() });
+        };
+
+        if self.bodies.is_empty() {
+            issue("bodies".into(), "a system needs at least one body");
+        }
+        if self.bodies.iter().filter(|body| body.is_sun).count() > 1 {
+            issue("bodies".into(), "at most one body may be flagged is_sun");
+        }
+
+        let mut names = HashSet::new();
+        let parents: HashMap<&str, Option<&str>> = self
+            .bodies
+            .iter()
+            .map(|body| (body.name.as_str(), body.parent.as_deref()))
+            .collect();
+
+        for (index, body) in self.bodies.iter().enumerate() {
+            let path = format!("bodies[{}]", index);
+
+            if body.name.trim().is_empty() {
+                issue(format!("{}.name", path), "name must not be empty");
+            } else if !names.insert(body.name.as_str()) {
+                issue(format!("{}.name", path), "name is already used by another body");
+            }
+            if !(body.radius.is_finite() && body.radius > 0.0) {
+                issue(format!("{}.radius", path), "radius must be a positive number");
+            }
+            if body.color.iter().any(|c| !(0.0..=1.0).contains(c)) {
+                issue(format!("{}.color", path), "color components must be between 0 and 1");
+            }
+            if body.orbit_color.is_some_and(|color| color.iter().any(|c| !(0.0..=1.0).contains(c))) {
+                issue(format!("{}.orbit_color", path), "color components must be between 0 and 1");
+            }
+
+            if body.trail_length.is_some_and(|days| !(days.is_finite() && days >= 0.0)) {
+                issue(format!("{}.trail_length", path), "trail length must be zero or a positive number of days");
+            }
+
+            if !body.rotation_period.is_finite() {
+                issue(format!("{}.rotation_period", path), "rotation period must be a finite number of days");
+            }
+            for (name, value) in [("axial_tilt", body.axial_tilt), ("rotation_phase", body.rotation_phase)] {
+                if !value.is_finite() {
+                    issue(format!("{}.{}", path, name), "angle must be a finite number of degrees");
+                }
+            }
+
+            if let Some(rings) = &body.rings {
+                let path = format!("{}.rings", path);
+                if !(rings.inner_radius.is_finite() && rings.inner_radius >= 0.0) {
+                    issue(format!("{}.inner_radius", path), "inner radius must be zero or positive");
+                }
+                if !(rings.outer_radius.is_finite() && rings.outer_radius > rings.inner_radius) {
+                    issue(format!("{}.outer_radius", path), "outer radius must be larger than the inner radius");
+                }
+                if rings.color.is_some_and(|color| color.iter().any(|c| !(0.0..=1.0).contains(c))) {
+                    issue(format!("{}.color", path), "color components must be between 0 and 1");
+                }
+                if !(0.0..=1.0).contains(&rings.opacity) {
+                    issue(format!("{}.opacity", path), "opacity must be between 0 and 1");
+                }
+            }
+
+            let palette = body.surface.as_ref().and_then(|surface| surface.palette);
+            if palette.is_some_and(|palette| palette.iter().flatten().any(|c| !(0.0..=1.0).contains(c))) {
+                issue(format!("{}.surface.palette", path), "color components must be between 0 and 1");
+            }
+
+            if let Some(parent) = &body.parent {
+                if !parents.contains_key(parent.as_str()) {
+                    issue(format!("{}.parent", path), "parent does not name a body in this system");
+                } else if body.is_sun {
+                    issue(format!("{}.parent", path), "the sun cannot orbit another body");
+                } else {
+                    // Walk up the chain; more steps than bodies means a cycle
+                    let mut current = Some(parent.as_str());
+                    let mut steps = 0;
+                    while let Some(name) = current {
+                        if name == body.name || steps > self.bodies.len() {
+                            issue(format!("{}.parent", path), "parent chain loops back on itself");
+                            break;
+                        }
+                        current = parents.get(name).copied().flatten();
+                        steps += 1;
+                    }
+                }
+            }
+
+            if let Some(orbit) = &body.orbit {
+                let path = format!("{}.orbit", path);
+                if !(orbit.semi_major_axis.is_finite() && orbit.semi_major_axis >= 0.0) {
+                    issue(format!("{}.semi_major_axis", path), "semi-major axis must be zero or positive");
+                }
+                if !(0.0..1.0).contains(&orbit.eccentricity) {
+                    issue(format!("{}.eccentricity", path), "only elliptical orbits (0 <= e < 1) are supported");
+                }
+                if !(orbit.period.is_finite() && orbit.period >= 0.0) {
+                    issue(format!("{}.period", path), "period must be zero or a positive number of days");
+                }
+                let angles = [
+                    ("inclination", orbit.inclination),
+                    ("longitude_of_ascending_node", orbit.longitude_of_ascending_node),
+                    ("argument_of_periapsis", orbit.argument_of_periapsis),
+                    ("mean_anomaly_at_epoch", orbit.mean_anomaly_at_epoch),
+                ];
+                for (name, value) in angles {
+                    if !value.is_finite() {
+                        issue(format!("{}.{}", path, name), "angle must be a finite number of degrees");
+                    }
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(description: Result<SystemDescription, SceneError>) -> Vec<(String, String)> {
+        match description.unwrap().build() {
+            Err(SceneError::Invalid(issues)) => issues.into_iter().map(|issue| (issue.path, issue.message)).collect(),
+            Err(other) => panic!("expected validation issues, got {:?}", other),
+            Ok(_) => panic!("expected validation issues, but the system built"),
+        }
+    }
+
+    fn has_issue(issues: &[(String, String)], path: &str, message: &str) -> bool {
+        issues.iter().any(|(p, m)| p == path && m.contains(message))
+    }
+
+    #[test]
+    fn a_valid_system_builds() {
+        let bodies = SystemDescription::from_toml(
+            r#"
+            [[bodies]]
+            name = "Star"
+            radius = 0.1
+            is_sun = true
+
+            [[bodies]]
+            name = "Planet"
+            radius = 0.02
+            parent = "Star"
+            orbit = { semi_major_axis = 1.0, eccentricity = 0.1, period = 365.0 }
+
+            [[bodies]]
+            name = "Moon"
+            radius = 0.005
+            parent = "Planet"
+            orbit = { semi_major_axis = 0.01, period = 27.0 }
+            "#,
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+
+        assert_eq!(bodies.len(), 3);
+        assert_eq!(bodies[2].parent, Some(1));
+    }
+
+    #[test]
+    fn bad_values_are_reported_at_their_paths() {
+        let found = issues(SystemDescription::from_json(
+            r#"{ "bodies": [
+                { "name": "Star", "radius": 0.1, "is_sun": true },
+                { "name": "Planet", "radius": -1.0, "parent": "Star",
+                  "orbit": { "semi_major_axis": 1.0, "period": 365.0 } },
+                { "name": "Comet", "radius": 0.01, "parent": "Star",
+                  "orbit": { "semi_major_axis": 5.0, "eccentricity": 1.2, "period": 4000.0 } },
+                { "name": "Stray", "radius": 0.01, "parent": "Nowhere" }
+            ] }"#,
+        ));
+
+        assert!(has_issue(&found, "bodies[1].radius", "positive"));
+        assert!(has_issue(&found, "bodies[2].orbit.eccentricity", "elliptical"));
+        assert!(has_issue(&found, "bodies[3].parent", "does not name a body"));
+        assert_eq!(found.len(), 3, "{:?}", found);
+    }
+
+    #[test]
+    fn parent_cycles_are_reported() {
+        let found = issues(SystemDescription::from_toml(
+            r#"
+            [[bodies]]
+            name = "A"
+            radius = 1.0
+            parent = "B"
+
+            [[bodies]]
+            name = "B"
+            radius = 1.0
+            parent = "A"
+
+            [[bodies]]
+            name = "C"
+            radius = 1.0
+            parent = "C"
+            "#,
+        ));
+
+        for path in ["bodies[0].parent", "bodies[1].parent", "bodies[2].parent"] {
+            assert!(has_issue(&found, path, "loops back"), "{:?}", found);
+        }
+    }
+
+    #[test]
+    fn unknown_fields_fail_to_parse() {
+        let json = SystemDescription::from_json(r#"{ "bodies": [{ "name": "Star", "radius": 1.0, "mass": 2.0 }] }"#);
+        assert!(matches!(json, Err(SceneError::Parse(message)) if message.contains("mass")));
+
+        let toml = SystemDescription::from_toml("[[bodies]]\nname = \"Star\"\nradius = 1.0\n[bodies.orbit]\nsemi_major_axis = 1.0\nperiod = 1.0\necc = 0.1\n");
+        assert!(matches!(toml, Err(SceneError::Parse(message)) if message.contains("ecc")));
+    }
+}
@@ -1,53 +1,133 @@
-use web_sys::{WebGlProgram, WebGlRenderingContext, WebGlShader};
-
-pub fn compile_shader(
-    context: &WebGlRenderingContext,
-    shader_type: u32,
-    source: &str,
-) -> Result<WebGlShader, String> {
-    let shader = context
-        .create_shader(shader_type)
-        .ok_or_else(|| String::from("Unable to create shader object"))?;
-    context.shader_source(&shader, source);
-    context.compile_shader(&shader);
-
-    if context
-        .get_shader_parameter(&shader, WebGlRenderingContext::COMPILE_STATUS)
-        .as_bool()
-        .unwrap_or(false)
-    {
-        Ok(shader)
-    } else {
-        Err(context
-            .get_shader_info_log(&shader)
-            .unwrap_or_else(|| String::from("Unknown error creating shader")))
+/// Every shader program the engine draws with. GL backends compile the GLSL below;
+/// other backends provide their own implementation of each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShaderKind {
+    Basic,     // Flat 2D shapes: `position`, `matrix`, `uColor`
+    Lit,       // Blinn-Phong bodies with optional diffuse maps
+    Line,      // Solid-color lines, for constellations
+    Orbit,     // Instanced orbit ellipses, one unit circle stretched per instance
+    Ring,      // Translucent planetary rings with the planet's shadow
+    Trail,     // Lines fading with age
+    Sky,       // Milky Way dome
+    Starfield, // Round point sprites
+}
+
+impl ShaderKind {
+    pub const ALL: [ShaderKind; 8] = [
+        ShaderKind::Basic,
+        ShaderKind::Lit,
+        ShaderKind::Line,
+        ShaderKind::Orbit,
+        ShaderKind::Ring,
+        ShaderKind::Trail,
+        ShaderKind::Sky,
+        ShaderKind::Starfield,
+    ];
+
+    /// Vertex and fragment source in GLSL ES 1.00
+    pub fn glsl(self) -> (&'static str, &'static str) {
+        match self {
+            ShaderKind::Basic => (VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE),
+            ShaderKind::Lit => (LIT_VERTEX_SHADER, LIT_FRAGMENT_SHADER),
+            ShaderKind::Line => (LINE_VERTEX_SHADER, LINE_FRAGMENT_SHADER),
+            ShaderKind::Orbit => (ORBIT_VERTEX_SHADER, ORBIT_FRAGMENT_SHADER),
+            ShaderKind::Ring => (RING_VERTEX_SHADER, RING_FRAGMENT_SHADER),
+            ShaderKind::Trail => (TRAIL_VERTEX_SHADER, TRAIL_FRAGMENT_SHADER),
+            ShaderKind::Sky => (SKY_VERTEX_SHADER, SKY_FRAGMENT_SHADER),
+            ShaderKind::Starfield => (STARFIELD_VERTEX_SHADER, STARFIELD_FRAGMENT_SHADER),
+        }
+    }
+
+    /// Vertex and fragment source in GLSL ES 3.00, translated from `glsl`, with the
+    /// uniforms listed in `UNIFORM_BLOCKS` declared as blocks instead
+    pub fn glsl_300(self) -> (String, String) {
+        let (vertex, fragment) = self.glsl();
+        (translate_to_300(vertex, true), translate_to_300(fragment, false))
     }
 }
 
-pub fn link_program(
-    context: &WebGlRenderingContext,
-    vert_shader: &WebGlShader,
-    frag_shader: &WebGlShader,
-) -> Result<WebGlProgram, String> {
-    let program = context
-        .create_program()
-        .ok_or_else(|| String::from("Unable to create shader object"))?;
-
-    context.attach_shader(&program, vert_shader);
-    context.attach_shader(&program, frag_shader);
-    context.link_program(&program);
-
-    if context
-        .get_program_parameter(&program, WebGlRenderingContext::LINK_STATUS)
-        .as_bool()
-        .unwrap_or(false)
-    {
-        Ok(program)
-    } else {
-        Err(context
-            .get_program_info_log(&program)
-            .unwrap_or_else(|| String::from("Unknown error creating program object")))
+/// A std140 uniform block shared by every program that declares it
+pub struct UniformBlock {
+    pub name: &'static str,
+    pub binding: u32, // Uniform buffer binding point
+    pub size: usize,  // Floats, padded to a whole vec4
+    pub members: &'static [(&'static str, &'static str, usize)], // GLSL type, name, offset in floats
+}
+
+/// Uniforms that GLSL ES 3.00 programs read from shared blocks: camera data and the light
+pub const UNIFORM_BLOCKS: [UniformBlock; 2] = [
+    UniformBlock {
+        name: "Camera",
+        binding: 0,
+        size: 36,
+        members: &[
+            ("mat4", "u_view_matrix", 0),
+            ("mat4", "u_projection_matrix", 16),
+            ("vec3", "u_camera_position", 32),
+        ],
+    },
+    UniformBlock {
+        name: "Light",
+        binding: 1,
+        size: 12,
+        members: &[
+            ("vec3", "u_light_position", 0),
+            ("vec3", "u_light_color", 4),
+            ("float", "u_light_intensity", 7), // std140 packs a float into the end of a vec3
+            ("float", "u_ambient", 8),
+            ("float", "u_specular", 9),
+            ("float", "u_shininess", 10),
+        ],
+    },
+];
+
+/// The block `name` belongs to, if any
+fn uniform_block(name: &str) -> Option<&'static UniformBlock> {
+    UNIFORM_BLOCKS
+        .iter()
+        .find(|block| block.members.iter().any(|(_, member, _)| *member == name))
+}
+
+/// Rewrite one stage of GLSL ES 1.00 as 3.00. Relies on the sources above declaring
+/// attributes, varyings and uniforms one per line, unindented.
+fn translate_to_300(source: &str, vertex: bool) -> String {
+    let mut lines = vec!["#version 300 es".to_string()];
+    let mut declared_blocks: Vec<&str> = Vec::new();
+
+    for line in source.lines() {
+        // Block members are declared once, as the whole block, where the first of them was
+        let uniform_name = line
+            .strip_prefix("uniform ")
+            .and_then(|declaration| declaration.split_whitespace().nth(1))
+            .map(|name| name.trim_end_matches(';'));
+        if let Some(block) = uniform_name.and_then(uniform_block) {
+            if !declared_blocks.contains(&block.name) {
+                declared_blocks.push(block.name);
+                lines.push(format!("layout(std140) uniform {} {{", block.name));
+                for (glsl_type, member, _) in block.members {
+                    lines.push(format!("    highp {glsl_type} {member};"));
+                }
+                lines.push("};".to_string());
+            }
+            continue;
+        }
+
+        let line = if let Some(rest) = line.strip_prefix("attribute ") {
+            format!("in {rest}")
+        } else if let Some(rest) = line.strip_prefix("varying ") {
+            format!("{} {rest}", if vertex { "out" } else { "in" })
+        } else {
+            line.replace("texture2D(", "texture(").replace("gl_FragColor", "frag_color")
+        };
+
+        if !vertex && line.starts_with("void main") {
+            lines.push("out vec4 frag_color;".to_string());
+            lines.push(String::new());
+        }
+        lines.push(line);
     }
+
+    lines.join("\n")
 }
 
 pub const VERTEX_SHADER_SOURCE: &str = r#"
@@ -71,15 +151,318 @@ void main() {
 }
 "#;
 
+pub const LIT_VERTEX_SHADER: &str = r#"
+attribute vec3 position;
+attribute vec3 normal;
+attribute vec2 uv;
+
+uniform mat4 u_model_matrix;
+uniform mat4 u_view_matrix;
+uniform mat4 u_projection_matrix;
+uniform mat3 u_normal_matrix;
+
+varying vec3 v_world_position;
+varying vec3 v_normal;
+varying vec2 v_uv;
+
+void main() {
+    vec4 world_position = u_model_matrix * vec4(position, 1.0);
+    v_world_position = world_position.xyz;
+    v_normal = u_normal_matrix * normal;
+    v_uv = uv;
+    gl_Position = u_projection_matrix * u_view_matrix * world_position;
+}
+"#;
+
+pub const LIT_FRAGMENT_SHADER: &str = r#"
+precision mediump float;
+
+varying vec3 v_world_position;
+varying vec3 v_normal;
+varying vec2 v_uv;
+
+uniform vec3 u_color;
+uniform bool u_emissive;
+uniform bool u_has_diffuse_map;
+uniform sampler2D u_diffuse_map;
+uniform vec3 u_light_position;
+uniform vec3 u_light_color;
+uniform float u_light_intensity;
+uniform float u_ambient;
+uniform float u_specular;
+uniform float u_shininess;
+uniform vec3 u_camera_position;
+
+void main() {
+    // A diffuse map replaces the flat body color
+    vec3 base_color = u_has_diffuse_map ? texture2D(u_diffuse_map, v_uv).rgb : u_color;
+
+    // Light sources glow with their own color and are not shaded
+    if (u_emissive) {
+        gl_FragColor = vec4(base_color, 1.0);
+        return;
+    }
+
+    vec3 normal = normalize(v_normal);
+    vec3 to_light = normalize(u_light_position - v_world_position);
+    vec3 to_camera = normalize(u_camera_position - v_world_position);
+    vec3 half_vector = normalize(to_light + to_camera);
+
+    // Blinn-Phong; no distance falloff since orbits are not to scale
+    vec3 light = u_light_color * u_light_intensity;
+    float diffuse = max(dot(normal, to_light), 0.0);
+    float specular = diffuse > 0.0 ? pow(max(dot(normal, half_vector), 0.0), u_shininess) : 0.0;
+
+    vec3 color = base_color * (u_ambient + diffuse * light) + u_specular * specular * light;
+    gl_FragColor = vec4(color, 1.0);
+}
+"#;
+
+pub const LINE_VERTEX_SHADER: &str = r#"
+attribute vec3 position;
+
+uniform mat4 u_model_matrix;
+uniform mat4 u_view_matrix;
+uniform mat4 u_projection_matrix;
+
+void main() {
+    gl_Position = u_projection_matrix * u_view_matrix * u_model_matrix * vec4(position, 1.0);
+}
+"#;
+
+pub const LINE_FRAGMENT_SHADER: &str = r#"
+precision mediump float;
+
+uniform vec4 u_color;
+
+void main() {
+    gl_FragColor = u_color;
+}
+"#;
+
+pub const ORBIT_VERTEX_SHADER: &str = r#"
+attribute vec3 position; // Unit circle: (cos E, sin E, 0) for eccentric anomaly E
+attribute vec3 i_center;
+attribute vec3 i_major_axis;
+attribute vec3 i_minor_axis;
+attribute vec4 i_color;
+
+uniform mat4 u_view_matrix;
+uniform mat4 u_projection_matrix;
+
+varying vec4 v_color;
+
+void main() {
+    vec3 world_position = i_center + i_major_axis * position.x + i_minor_axis * position.y;
+    gl_Position = u_projection_matrix * u_view_matrix * vec4(world_position, 1.0);
+    v_color = i_color;
+}
+"#;
+
+pub const ORBIT_FRAGMENT_SHADER: &str = r#"
+precision mediump float;
+
+varying vec4 v_color;
+
+void main() {
+    gl_FragColor = v_color;
+}
+"#;
+
+pub const RING_VERTEX_SHADER: &str = r#"
+attribute vec3 position;
+attribute vec2 uv;
+
+uniform mat4 u_model_matrix;
+uniform mat4 u_view_matrix;
+uniform mat4 u_projection_matrix;
+
+varying vec3 v_world_position;
+varying float v_radial;
+
+void main() {
+    vec4 world_position = u_model_matrix * vec4(position, 1.0);
+    gl_Position = u_projection_matrix * u_view_matrix * world_position;
+    v_world_position = world_position.xyz;
+    v_radial = uv.x;
+}
+"#;
+
+pub const RING_FRAGMENT_SHADER: &str = r#"
+precision mediump float;
+
+varying vec3 v_world_position;
+varying float v_radial; // 0 at the inner edge, 1 at the outer edge
+
+uniform vec3 u_color;
+uniform float u_opacity;
+uniform vec3 u_light_position;
+uniform vec3 u_light_color;
+uniform float u_light_intensity;
+uniform float u_ambient;
+uniform bool u_shadow;
+uniform vec3 u_planet_center;
+uniform float u_planet_radius;
+
+void main() {
+    // Soft edges and a few density bands so the annulus does not read as a flat disc
+    float edges = smoothstep(0.0, 0.04, v_radial) * (1.0 - smoothstep(0.96, 1.0, v_radial));
+    float bands = 0.75 + 0.25 * sin(v_radial * 47.0) * sin(v_radial * 13.0);
+    float alpha = u_opacity * edges * bands;
+
+    // Rings scatter light to both sides, so only the planet's shadow matters
+    float lit = 1.0;
+    if (u_shadow) {
+        vec3 to_light = normalize(u_light_position - v_world_position);
+        vec3 to_planet = u_planet_center - v_world_position;
+        float along = dot(to_planet, to_light);
+        float miss = length(to_planet - to_light * along) / u_planet_radius;
+        if (along > 0.0) {
+            lit = smoothstep(0.95, 1.05, miss);
+        }
+    }
+
+    vec3 color = u_color * (u_ambient + lit * u_light_color * u_light_intensity);
+    gl_FragColor = vec4(color, alpha);
+}
+"#;
+
+pub const TRAIL_VERTEX_SHADER: &str = r#"
+attribute vec3 position;
+attribute float freshness; // 1.0 at the body, 0.0 at the end of the trail
+
+uniform mat4 u_view_matrix;
+uniform mat4 u_projection_matrix;
+
+varying float v_freshness;
+
+void main() {
+    gl_Position = u_projection_matrix * u_view_matrix * vec4(position, 1.0);
+    v_freshness = freshness;
+}
+"#;
+
+pub const TRAIL_FRAGMENT_SHADER: &str = r#"
+precision mediump float;
+
+uniform vec4 u_color;
+
+varying float v_freshness;
+
+void main() {
+    gl_FragColor = vec4(u_color.rgb, u_color.a * v_freshness * v_freshness);
+}
+"#;
+
+pub const SKY_VERTEX_SHADER: &str = r#"
+attribute vec3 position;
+
+uniform mat4 u_view_rotation; // View matrix without translation, so the dome is always around the camera
+uniform mat4 u_projection_matrix;
+
+varying vec3 v_direction;
+
+void main() {
+    v_direction = position;
+    gl_Position = u_projection_matrix * u_view_rotation * vec4(position, 1.0);
+}
+"#;
+
+pub const SKY_FRAGMENT_SHADER: &str = r#"
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+
+varying vec3 v_direction;
+
+uniform mat3 u_to_galactic;
+uniform float u_intensity;
+uniform bool u_nebulae;
+
+float hash(vec3 p) {
+    p = fract(p * 0.3183099 + 0.1);
+    p *= 17.0;
+    return fract(p.x * p.y * p.z * (p.x + p.y + p.z));
+}
+
+// Smooth value noise in 0..1
+float value_noise(vec3 x) {
+    vec3 i = floor(x);
+    vec3 f = fract(x);
+    f = f * f * (3.0 - 2.0 * f);
+    return mix(
+        mix(mix(hash(i), hash(i + vec3(1.0, 0.0, 0.0)), f.x),
+            mix(hash(i + vec3(0.0, 1.0, 0.0)), hash(i + vec3(1.0, 1.0, 0.0)), f.x), f.y),
+        mix(mix(hash(i + vec3(0.0, 0.0, 1.0)), hash(i + vec3(1.0, 0.0, 1.0)), f.x),
+            mix(hash(i + vec3(0.0, 1.0, 1.0)), hash(i + vec3(1.0, 1.0, 1.0)), f.x), f.y),
+        f.z);
+}
+
+float fbm(vec3 p) {
+    float sum = 0.0;
+    float amplitude = 0.5;
+    for (int i = 0; i < 5; i++) {
+        sum += amplitude * value_noise(p);
+        p *= 2.03;
+        amplitude *= 0.5;
+    }
+    return sum;
+}
+
+// Soft glow around galactic (l, b), in radians
+float glow_at(vec3 galactic, float l, float b, float radius) {
+    vec3 center = vec3(cos(b) * cos(l), cos(b) * sin(l), sin(b));
+    float angle = acos(clamp(dot(galactic, center), -1.0, 1.0));
+    return exp(-angle * angle / (radius * radius));
+}
+
+void main() {
+    vec3 galactic = normalize(u_to_galactic * normalize(v_direction));
+    float latitude = asin(clamp(galactic.z, -1.0, 1.0));
+    float toward_center = galactic.x * 0.5 + 0.5; // 1 at the galactic center, 0 opposite
+
+    // Thin disk everywhere, thicker and brighter toward the bulge in Sagittarius
+    float width = mix(0.12, 0.25, toward_center * toward_center);
+    float disk = exp(-latitude * latitude / (width * width));
+    float bulge = glow_at(galactic, 0.0, 0.0, 0.35);
+    float clouds = fbm(galactic * 6.0);
+    float glow = (disk * (0.45 + 0.55 * toward_center) + bulge) * (0.55 + 0.9 * clouds);
+
+    // Dust lanes hug the plane and eat into the glow, like the Great Rift
+    float dust = smoothstep(0.45, 0.75, fbm(galactic * 9.0 + vec3(7.1))) * exp(-latitude * latitude / 0.004);
+    glow *= 1.0 - 0.8 * dust;
+
+    vec3 color = glow * mix(vec3(0.55, 0.6, 0.75), vec3(0.95, 0.85, 0.7), bulge + 0.3 * toward_center);
+
+    if (u_nebulae) {
+        float detail = fbm(galactic * 14.0);
+        color += vec3(0.9, 0.25, 0.3) * glow_at(galactic, radians(-150.4), radians(-19.4), 0.12) * detail; // Orion
+        color += vec3(0.85, 0.3, 0.4) * glow_at(galactic, radians(6.0), radians(-1.2), 0.06) * detail;     // Lagoon
+        color += vec3(0.3, 0.5, 0.9) * glow_at(galactic, radians(-193.3), radians(-23.4), 0.05) * detail;  // Pleiades
+        color += vec3(0.8, 0.35, 0.35) * glow_at(galactic, radians(-72.4), radians(-0.6), 0.1) * detail;   // Carina
+        color += vec3(0.8, 0.3, 0.35) * glow_at(galactic, radians(85.0), radians(-1.0), 0.12) * detail;    // North America
+    }
+
+    // Composite over the background as if added to it
+    color *= u_intensity;
+    float alpha = clamp(max(color.r, max(color.g, color.b)), 0.0, 1.0);
+    gl_FragColor = vec4(alpha > 0.0 ? color / alpha : vec3(0.0), alpha);
+}
+"#;
+
 pub const STARFIELD_VERTEX_SHADER: &str = r#"
 attribute vec3 a_star_position;
 attribute float a_brightness;
 attribute float a_size;
+attribute vec3 a_color;
 
 uniform mat4 u_view_matrix;
 uniform mat4 u_projection_matrix;
 
 varying float v_brightness;
+varying vec3 v_color;
 
 void main() {
     gl_Position = u_projection_matrix * u_view_matrix * vec4(a_star_position, 1.0);
@@ -87,6 +470,7 @@ void main() {
     float distance = length(gl_Position.xyz);
     gl_PointSize = a_size * 250.0 / distance;  // Much bigger size multiplier
     v_brightness = a_brightness;
+    v_color = a_color;
 }
 "#;
 
@@ -94,6 +478,7 @@ pub const STARFIELD_FRAGMENT_SHADER: &str = r#"
 precision mediump float;
 
 varying float v_brightness;
+varying vec3 v_color;
 
 void main() {
     // Create circular star shape
@@ -110,16 +495,35 @@ void main() {
         intensity = pow(intensity, 0.8);  // Softer for dim stars
     }
     
-    // Brighter star colors - more white for bright stars
-    vec3 starColor = mix(
-        vec3(0.9, 0.88, 0.85),  // Dim star color
-        vec3(1.0, 0.98, 0.95),  // Bright star color
-        v_brightness
-    );
+    // Blackbody color, washing out to white in the core of bright stars
+    vec3 starColor = mix(v_color, vec3(1.0), 0.5 * pow(intensity, 4.0) * v_brightness);
     
     // Higher alpha for bright stars
     float alpha = intensity * (0.5 + 0.5 * v_brightness);
     
     gl_FragColor = vec4(starColor, alpha);
 }
-"#;
\ No newline at end of file
+"#;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_block_members_fit_their_blocks() {
+        for block in &UNIFORM_BLOCKS {
+            assert_eq!(block.size % 4, 0, "{} is not padded to a vec4", block.name);
+            for &(glsl_type, name, offset) in block.members {
+                // std140: matrix columns are padded to vec4s
+                let floats = match glsl_type {
+                    "float" => 1,
+                    "vec3" => 3,
+                    "vec4" => 4,
+                    "mat3" => 12,
+                    "mat4" => 16,
+                    other => panic!("{} has unsupported type {}", name, other),
+                };
+                assert!(offset + floats <= block.size, "{} does not fit in {}", name, block.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+/// Optional per-vertex attributes carried alongside positions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VertexAttributes {
+    pub normals: bool,
+    pub uvs: bool,
+    pub colors: bool,
+}
+
+/// How `Geometry::indices` are assembled into primitives
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Primitive {
+    #[default]
+    Triangles,
+    Lines,
+    LineLoop,
+    LineStrip,
+    Points,
+}
+
+/// One interleaved attribute: shader name, component count and byte offset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttributeLayout {
+    pub name: &'static str,
+    pub size: i32,
+    pub offset: i32,
+}
+
+/// Indexed geometry on the CPU, ready to be uploaded as a `Mesh`
+#[derive(Clone, Debug, Default)]
+pub struct Geometry {
+    pub positions: Vec<f32>,      // x, y, z per vertex
+    pub normals: Vec<f32>,        // x, y, z per vertex, or empty
+    pub uvs: Vec<f32>,            // u, v per vertex, or empty
+    pub colors: Option<Vec<f32>>, // r, g, b per vertex
+    pub indices: Vec<u16>,        // three per triangle, two per line, or one per strip/loop vertex
+    pub primitive: Primitive,
+}
+
+impl Geometry {
+    /// Closed polyline through `positions` (x, y, z per vertex)
+    pub fn line_loop(positions: Vec<f32>) -> Self {
+        let indices = (0..(positions.len() / 3) as u16).collect();
+        Self {
+            positions,
+            indices,
+            primitive: Primitive::LineLoop,
+            ..Self::default()
+        }
+    }
+
+    /// Separate segments, one per consecutive pair of vertices in `positions`
+    pub fn lines(positions: Vec<f32>) -> Self {
+        Self {
+            primitive: Primitive::Lines,
+            ..Self::line_loop(positions)
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len() / 3
+    }
+
+    pub fn attributes(&self) -> VertexAttributes {
+        VertexAttributes {
+            normals: !self.normals.is_empty(),
+            uvs: !self.uvs.is_empty(),
+            colors: self.colors.is_some(),
+        }
+    }
+
+    /// Attribute layout of `interleaved`, in floats per vertex and byte offsets
+    pub fn layout(&self) -> (Vec<AttributeLayout>, i32) {
+        let attributes = self.attributes();
+        let mut layout = vec![AttributeLayout { name: "position", size: 3, offset: 0 }];
+        let mut floats = 3;
+
+        for (present, name, size) in [
+            (attributes.normals, "normal", 3),
+            (attributes.uvs, "uv", 2),
+            (attributes.colors, "color", 3),
+        ] {
+            if present {
+                layout.push(AttributeLayout { name, size, offset: floats * 4 });
+                floats += size;
+            }
+        }
+
+        (layout, floats * 4)
+    }
+
+    /// Vertex data with every attribute packed per vertex, matching `layout`
+    pub fn interleaved(&self) -> Vec<f32> {
+        let (_, stride) = self.layout();
+        let mut vertices = Vec::with_capacity(self.vertex_count() * stride as usize / 4);
+
+        for i in 0..self.vertex_count() {
+            vertices.extend_from_slice(&self.positions[i * 3..i * 3 + 3]);
+            if !self.normals.is_empty() {
+                vertices.extend_from_slice(&self.normals[i * 3..i * 3 + 3]);
+            }
+            if !self.uvs.is_empty() {
+                vertices.extend_from_slice(&self.uvs[i * 2..i * 2 + 2]);
+            }
+            if let Some(colors) = &self.colors {
+                vertices.extend_from_slice(&colors[i * 3..i * 3 + 3]);
+            }
+        }
+
+        vertices
+    }
+
+    /// Each unique triangle edge once, for drawing as `LINES`. Line geometry is
+    /// already a wireframe and is returned unchanged.
+    pub fn wireframe_indices(&self) -> Vec<u16> {
+        if self.primitive != Primitive::Triangles {
+            return self.indices.clone();
+        }
+
+        let mut seen = HashSet::new();
+        let mut lines = Vec::new();
+
+        for triangle in self.indices.chunks_exact(3) {
+            for (a, b) in [(triangle[0], triangle[1]), (triangle[1], triangle[2]), (triangle[2], triangle[0])] {
+                if seen.insert((a.min(b), a.max(b))) {
+                    lines.extend_from_slice(&[a, b]);
+                }
+            }
+        }
+
+        lines
+    }
+}
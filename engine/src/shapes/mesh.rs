@@ -0,0 +1,130 @@
+use crate::backend::{BufferData, BufferHandle, BufferUsage, DrawCall, GraphicsBackend, ProgramHandle, VertexAttribute};
+use super::geometry::{AttributeLayout, Geometry, Primitive};
+
+/// Indexed geometry that has been uploaded to the GPU once and can be drawn every frame
+pub struct Mesh {
+    vertex_buffer: BufferHandle,
+    index_buffer: BufferHandle,
+    wireframe_index_buffer: BufferHandle,
+    index_count: usize,
+    wireframe_index_count: usize,
+    layout: Vec<AttributeLayout>,
+    stride: i32,
+    primitive: Primitive,
+}
+
+impl Mesh {
+    fn upload(backend: &dyn GraphicsBackend, geometry: &Geometry) -> Result<Self, String> {
+        let vertices = geometry.interleaved();
+        let vertex_buffer = backend.create_buffer(BufferData::Vertices(&vertices), BufferUsage::Static)?;
+
+        let wireframe_indices = geometry.wireframe_indices();
+        let index_buffer = backend.create_buffer(BufferData::Indices(&geometry.indices), BufferUsage::Static)?;
+        let wireframe_index_buffer = backend.create_buffer(BufferData::Indices(&wireframe_indices), BufferUsage::Static)?;
+        let (layout, stride) = geometry.layout();
+
+        Ok(Self {
+            vertex_buffer,
+            index_buffer,
+            wireframe_index_buffer,
+            index_count: geometry.indices.len(),
+            wireframe_index_count: wireframe_indices.len(),
+            layout,
+            stride,
+            primitive: geometry.primitive,
+        })
+    }
+
+    /// Draw with its own primitive, or as its unique edges in wireframe mode, feeding
+    /// every attribute the program uses from the vertex buffer
+    pub fn draw(&self, backend: &dyn GraphicsBackend, program: ProgramHandle, wireframe: bool) {
+        self.draw_instanced(backend, program, wireframe, &[], 1);
+    }
+
+    /// Draw `instances` copies in one call, reading `instance_attributes` (with non-zero
+    /// divisors) alongside the mesh's own
+    pub fn draw_instanced(
+        &self,
+        backend: &dyn GraphicsBackend,
+        program: ProgramHandle,
+        wireframe: bool,
+        instance_attributes: &[VertexAttribute],
+        instances: usize,
+    ) {
+        let attributes: Vec<VertexAttribute> = self
+            .layout
+            .iter()
+            .map(|attribute| VertexAttribute {
+                name: attribute.name,
+                buffer: self.vertex_buffer,
+                size: attribute.size,
+                stride: self.stride,
+                offset: attribute.offset,
+                divisor: 0,
+            })
+            .chain(instance_attributes.iter().copied())
+            .collect();
+
+        let (primitive, indices, count) = if wireframe && self.primitive == Primitive::Triangles {
+            (Primitive::Lines, self.wireframe_index_buffer, self.wireframe_index_count)
+        } else {
+            (self.primitive, self.index_buffer, self.index_count)
+        };
+
+        backend.draw(&DrawCall {
+            program,
+            attributes: &attributes,
+            indices: Some(indices),
+            primitive,
+            first: 0,
+            count,
+            instances,
+        });
+    }
+
+    fn delete(&self, backend: &dyn GraphicsBackend) {
+        backend.delete_buffer(self.vertex_buffer);
+        backend.delete_buffer(self.index_buffer);
+        backend.delete_buffer(self.wireframe_index_buffer);
+    }
+}
+
+/// Lightweight reference to a mesh owned by a `MeshCache`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MeshHandle(usize);
+
+/// Owns every uploaded mesh so buffers are created once and freed explicitly
+#[derive(Default)]
+pub struct MeshCache {
+    meshes: Vec<Option<Mesh>>,
+}
+
+impl MeshCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Upload `geometry`, reusing a freed slot if there is one
+    pub fn upload(&mut self, backend: &dyn GraphicsBackend, geometry: &Geometry) -> Result<MeshHandle, String> {
+        let mesh = Mesh::upload(backend, geometry)?;
+
+        if let Some(index) = self.meshes.iter().position(Option::is_none) {
+            self.meshes[index] = Some(mesh);
+            Ok(MeshHandle(index))
+        } else {
+            self.meshes.push(Some(mesh));
+            Ok(MeshHandle(self.meshes.len() - 1))
+        }
+    }
+
+    pub fn get(&self, handle: MeshHandle) -> Option<&Mesh> {
+        self.meshes.get(handle.0).and_then(Option::as_ref)
+    }
+
+    /// Delete the GPU buffers behind `handle`; the handle must not be used afterwards
+    pub fn free(&mut self, backend: &dyn GraphicsBackend, handle: MeshHandle) {
+        if let Some(mesh) = self.meshes.get_mut(handle.0).and_then(Option::take) {
+            mesh.delete(backend);
+        }
+    }
+}
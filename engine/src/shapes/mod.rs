@@ -1,9 +1,14 @@
 pub mod traits;
+pub mod geometry;
+pub mod mesh;
 pub mod triangle;
 pub mod rectangle;
 pub mod sphere;
+pub mod ring;
 
 pub use traits::RenderableShape;
+pub use mesh::MeshCache;
 pub use triangle::Triangle;
 pub use rectangle::Rectangle;
-pub use sphere::Sphere;
\ No newline at end of file
+pub use sphere::Sphere;
+pub use ring::Ring;
\ No newline at end of file
@@ -1,49 +1,48 @@
-use web_sys::{WebGlProgram, WebGlRenderingContext};
-use super::traits::{RenderableShape, setup_vertex_buffer, set_uniforms};
+use crate::backend::GraphicsBackend;
+use super::geometry::{Geometry, Primitive};
+use super::mesh::{MeshCache, MeshHandle};
+use super::traits::RenderableShape;
 
 pub struct Rectangle {
-    vertices: [f32; 12],
+    mesh: MeshHandle,
 }
 
 impl Rectangle {
-    pub fn new() -> Self {
-        Self {
-            vertices: [
+    pub fn new(backend: &dyn GraphicsBackend, meshes: &mut MeshCache) -> Result<Self, String> {
+        Ok(Self {
+            mesh: meshes.upload(backend, &Self::geometry())?,
+        })
+    }
+
+    pub fn geometry() -> Geometry {
+        Geometry {
+            positions: vec![
                 -0.5, -0.5, 0.0,  // bottom-left
                  0.5, -0.5, 0.0,  // bottom-right
                  0.5,  0.5, 0.0,  // top-right
                 -0.5,  0.5, 0.0,  // top-left
             ],
+            normals: vec![
+                0.0, 0.0, 1.0,
+                0.0, 0.0, 1.0,
+                0.0, 0.0, 1.0,
+                0.0, 0.0, 1.0,
+            ],
+            uvs: vec![
+                0.0, 0.0,
+                1.0, 0.0,
+                1.0, 1.0,
+                0.0, 1.0,
+            ],
+            colors: None,
+            primitive: Primitive::Triangles,
+            indices: vec![0, 1, 2, 0, 2, 3],
         }
     }
 }
 
 impl RenderableShape for Rectangle {
-    fn render(
-        &self,
-        context: &WebGlRenderingContext,
-        program: &WebGlProgram,
-        _position: [f32; 3], // Position handled by matrix
-        color: [f32; 3],
-        matrix: &[f32; 16],
-        wireframe: bool,
-    ) {
-        // Set up vertex buffer
-        if let Err(e) = setup_vertex_buffer(context, program, &self.vertices) {
-            web_sys::console::error_1(&format!("Rectangle vertex buffer error: {}", e).into());
-            return;
-        }
-        
-        // Set uniforms
-        set_uniforms(context, program, matrix, color);
-        
-        // Draw
-        let draw_mode = if wireframe {
-            WebGlRenderingContext::LINE_LOOP
-        } else {
-            WebGlRenderingContext::TRIANGLE_FAN
-        };
-        
-        context.draw_arrays(draw_mode, 0, 4);
+    fn mesh(&self) -> MeshHandle {
+        self.mesh
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,59 @@
+use crate::backend::GraphicsBackend;
+use std::f32::consts::PI;
+use super::geometry::Geometry;
+use super::mesh::{MeshCache, MeshHandle};
+use super::traits::RenderableShape;
+
+pub struct Ring {
+    mesh: MeshHandle,
+}
+
+impl Ring {
+    pub fn new(
+        backend: &dyn GraphicsBackend,
+        meshes: &mut MeshCache,
+        inner_radius: f32,
+        outer_radius: f32,
+        segments: u32,
+    ) -> Result<Self, String> {
+        let geometry = Self::geometry(inner_radius, outer_radius, segments);
+        Ok(Self {
+            mesh: meshes.upload(backend, &geometry)?,
+        })
+    }
+
+    /// Flat annulus in the XZ plane facing +Y. `u` runs from 0 at the inner edge to 1 at
+    /// the outer edge, so a texture or shader can vary along the radius; `v` goes around.
+    pub fn geometry(inner_radius: f32, outer_radius: f32, segments: u32) -> Geometry {
+        let mut geometry = Geometry::default();
+
+        for i in 0..=segments {
+            let angle = i as f32 * 2.0 * PI / segments as f32;
+            let (sin, cos) = angle.sin_cos();
+            let v = i as f32 / segments as f32;
+
+            for (radius, u) in [(inner_radius, 0.0), (outer_radius, 1.0)] {
+                geometry.positions.extend_from_slice(&[radius * cos, 0.0, radius * sin]);
+                geometry.normals.extend_from_slice(&[0.0, 1.0, 0.0]);
+                geometry.uvs.extend_from_slice(&[u, v]);
+            }
+        }
+
+        // Counter-clockwise when seen from above
+        for i in 0..segments as u16 {
+            let inner = i * 2;
+            let outer = inner + 1;
+            let next_inner = inner + 2;
+            let next_outer = inner + 3;
+            geometry.indices.extend_from_slice(&[inner, next_inner, outer, outer, next_inner, next_outer]);
+        }
+
+        geometry
+    }
+}
+
+impl RenderableShape for Ring {
+    fn mesh(&self) -> MeshHandle {
+        self.mesh
+    }
+}
@@ -1,86 +1,92 @@
-use web_sys::{WebGlProgram, WebGlRenderingContext};
+use crate::backend::GraphicsBackend;
 use std::f32::consts::PI;
-use super::traits::{RenderableShape, setup_vertex_buffer, set_uniforms};
+use super::geometry::Geometry;
+use super::mesh::{MeshCache, MeshHandle};
+use super::traits::RenderableShape;
 
 pub struct Sphere {
-    vertices: Vec<f32>,
-    vertex_count: i32,
+    mesh: MeshHandle,
 }
 
 impl Sphere {
-    pub fn new(radius: f32, latitude_segments: u32, longitude_segments: u32) -> Self {
-        let mut vertices = Vec::new();
-        
-        // Generate triangles directly (no indices)
-        for lat in 0..latitude_segments {
-            let theta1 = lat as f32 * PI / latitude_segments as f32;
-            let theta2 = (lat + 1) as f32 * PI / latitude_segments as f32;
-            
-            let sin_theta1 = theta1.sin();
-            let cos_theta1 = theta1.cos();
-            let sin_theta2 = theta2.sin();
-            let cos_theta2 = theta2.cos();
-            
-            for lon in 0..longitude_segments {
-                let phi1 = lon as f32 * 2.0 * PI / longitude_segments as f32;
-                let phi2 = (lon + 1) as f32 * 2.0 * PI / longitude_segments as f32;
-                
-                let sin_phi1 = phi1.sin();
-                let cos_phi1 = phi1.cos();
-                let sin_phi2 = phi2.sin();
-                let cos_phi2 = phi2.cos();
-                
-                // First triangle
-                vertices.extend_from_slice(&[
-                    radius * sin_theta1 * cos_phi1, radius * cos_theta1, radius * sin_theta1 * sin_phi1,
-                    radius * sin_theta2 * cos_phi1, radius * cos_theta2, radius * sin_theta2 * sin_phi1,
-                    radius * sin_theta1 * cos_phi2, radius * cos_theta1, radius * sin_theta1 * sin_phi2,
-                ]);
-                
-                // Second triangle
-                vertices.extend_from_slice(&[
-                    radius * sin_theta2 * cos_phi1, radius * cos_theta2, radius * sin_theta2 * sin_phi1,
-                    radius * sin_theta2 * cos_phi2, radius * cos_theta2, radius * sin_theta2 * sin_phi2,
-                    radius * sin_theta1 * cos_phi2, radius * cos_theta1, radius * sin_theta1 * sin_phi2,
+    pub fn new(
+        backend: &dyn GraphicsBackend,
+        meshes: &mut MeshCache,
+        radius: f32,
+        latitude_segments: u32,
+        longitude_segments: u32,
+    ) -> Result<Self, String> {
+        let geometry = Self::geometry(radius, latitude_segments, longitude_segments)?;
+        Ok(Self {
+            mesh: meshes.upload(backend, &geometry)?,
+        })
+    }
+
+    /// UV sphere with the poles on the Y axis. `u` increases eastward (counterclockwise
+    /// seen from +Y) so equirectangular maps are not mirrored, and the seam column is
+    /// duplicated so texture coordinates wrap cleanly from u = 1 back to u = 0.
+    /// Needs at least 2 latitude and 3 longitude segments, and few enough vertices
+    /// for every index to fit in a `u16`.
+    pub fn geometry(radius: f32, latitude_segments: u32, longitude_segments: u32) -> Result<Geometry, String> {
+        if latitude_segments < 2 || longitude_segments < 3 {
+            return Err(format!(
+                "A sphere needs at least 2 latitude and 3 longitude segments, got {}x{}",
+                latitude_segments, longitude_segments
+            ));
+        }
+        let vertex_count = (latitude_segments as u64 + 1) * (longitude_segments as u64 + 1);
+        if vertex_count > u16::MAX as u64 + 1 {
+            return Err(format!(
+                "A {}x{} sphere has {} vertices, more than 16-bit indices can address",
+                latitude_segments, longitude_segments, vertex_count
+            ));
+        }
+
+        let mut geometry = Geometry::default();
+
+        for lat in 0..=latitude_segments {
+            let theta = lat as f32 * PI / latitude_segments as f32;
+            let sin_theta = theta.sin();
+            let cos_theta = theta.cos();
+
+            for lon in 0..=longitude_segments {
+                let phi = lon as f32 * 2.0 * PI / longitude_segments as f32;
+                let normal = [sin_theta * phi.cos(), cos_theta, -sin_theta * phi.sin()];
+
+                geometry.positions.extend_from_slice(&normal.map(|n| n * radius));
+                geometry.normals.extend_from_slice(&normal);
+                geometry.uvs.extend_from_slice(&[
+                    lon as f32 / longitude_segments as f32,
+                    1.0 - lat as f32 / latitude_segments as f32,
                 ]);
             }
         }
-        
-        let vertex_count = (vertices.len() / 3) as i32;
-        
-        Self {
-            vertices,
-            vertex_count,
+
+        // Counter-clockwise when seen from outside
+        let row = longitude_segments + 1;
+        for lat in 0..latitude_segments {
+            for lon in 0..longitude_segments {
+                let top_left = (lat * row + lon) as u16;
+                let top_right = top_left + 1;
+                let bottom_left = top_left + row as u16;
+                let bottom_right = bottom_left + 1;
+
+                // The rows at the poles collapse to a point, so skip their degenerate halves
+                if lat != 0 {
+                    geometry.indices.extend_from_slice(&[top_left, bottom_left, top_right]);
+                }
+                if lat != latitude_segments - 1 {
+                    geometry.indices.extend_from_slice(&[bottom_left, bottom_right, top_right]);
+                }
+            }
         }
+
+        Ok(geometry)
     }
 }
 
 impl RenderableShape for Sphere {
-    fn render(
-        &self,
-        context: &WebGlRenderingContext,
-        program: &WebGlProgram,
-        _position: [f32; 3], // Position handled by matrix
-        color: [f32; 3],
-        matrix: &[f32; 16],
-        wireframe: bool,
-    ) {
-        // Set up vertex buffer
-        if let Err(e) = setup_vertex_buffer(context, program, &self.vertices) {
-            web_sys::console::error_1(&format!("Sphere vertex buffer error: {}", e).into());
-            return;
-        }
-        
-        // Set uniforms
-        set_uniforms(context, program, matrix, color);
-        
-        // Draw
-        let draw_mode = if wireframe {
-            WebGlRenderingContext::LINE_STRIP
-        } else {
-            WebGlRenderingContext::TRIANGLES
-        };
-        
-        context.draw_arrays(draw_mode, 0, self.vertex_count);
+    fn mesh(&self) -> MeshHandle {
+        self.mesh
     }
-}
\ No newline at end of file
+}
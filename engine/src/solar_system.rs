@@ -1,52 +1,134 @@
-use std::f32::consts::PI;
+use crate::clock::{SimulationClock, J2000};
+use crate::math::{Quat, Vec3};
+use crate::orbit::OrbitalElements;
+use crate::procedural::{Surface, SurfaceKind};
+use crate::trail::{Trail, TRAIL_SAMPLES};
+
+/// Flat rings in a body's equatorial plane
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rings {
+    pub inner_radius: f32,     // In units of the body's radius
+    pub outer_radius: f32,
+    pub color: [f32; 3],
+    pub opacity: f32,
+    pub receives_shadow: bool, // Darken where the body blocks the sun
+}
 
 #[derive(Clone)]
 pub struct CelestialBody {
     pub name: String,
     pub radius: f32,           // Relative size
-    pub orbit_radius: f32,     // Distance from sun (0 for sun)
-    pub orbit_speed: f32,      // Radians per frame
-    pub current_angle: f32,    // Current position in orbit
+    pub orbit: OrbitalElements,
+    pub orbital_period: f64,   // Days per orbit; 0 keeps the body fixed
     pub color: [f32; 3],
     pub is_sun: bool,
+    pub parent: Option<usize>, // Index of the body this one orbits; None orbits the origin
+    pub orbit_color: [f32; 4], // RGBA of the orbit path
+    pub show_orbit: bool,
+    pub trail_length: f64,     // Days of past motion kept in the trail; 0 disables it
+    pub trail: Trail,
+    pub axial_tilt: f32,       // Radians between the spin axis and ecliptic north; over 90 degrees spins retrograde
+    pub rotation_period: f64,  // Sidereal days per turn; 0 does not spin
+    pub rotation_phase: f32,   // Radians turned at J2000
+    pub rings: Option<Rings>,
+    pub surface: Option<Surface>, // Procedural surface map; None draws the flat color
+    position: [f32; 3],        // World position, resolved through the parent chain
 }
 
 impl CelestialBody {
-    pub fn new(name: &str, radius: f32, orbit_radius: f32, orbit_speed: f32, color: [f32; 3], is_sun: bool) -> Self {
+    pub fn new(name: &str, radius: f32, orbit: OrbitalElements, orbital_period: f64, color: [f32; 3], is_sun: bool) -> Self {
         Self {
             name: name.to_string(),
             radius,
-            orbit_radius,
-            orbit_speed,
-            current_angle: 0.0,
+            orbit,
+            orbital_period,
             color,
             is_sun,
+            parent: None,
+            orbit_color: [color[0], color[1], color[2], 0.35],
+            show_orbit: !is_sun,
+            trail_length: (orbital_period * 0.5).min(365.0),
+            trail: Trail::default(),
+            axial_tilt: 0.0,
+            rotation_period: 0.0,
+            rotation_phase: 0.0,
+            rings: None,
+            surface: None,
+            position: [0.0, 0.0, 0.0],
         }
     }
     
-    pub fn update(&mut self, delta_time: f32, time_scale: f32) {
-        if !self.is_sun {
-            self.current_angle += self.orbit_speed * delta_time * time_scale;
-            if self.current_angle > 2.0 * PI {
-                self.current_angle -= 2.0 * PI;
-            }
-        }
+    /// Make this body orbit `parent` (an index into `SolarSystem::bodies`) instead of the origin
+    pub fn orbiting(mut self, parent: usize) -> Self {
+        self.parent = Some(parent);
+        self
     }
     
-    pub fn get_position(&self) -> [f32; 3] {
+    /// Spin about a tilted axis. Angles are in degrees, the period in sidereal days.
+    pub fn spinning(mut self, axial_tilt: f32, rotation_period: f64, rotation_phase: f32) -> Self {
+        self.axial_tilt = axial_tilt.to_radians();
+        self.rotation_period = rotation_period;
+        self.rotation_phase = rotation_phase.to_radians();
+        self
+    }
+    
+    pub fn with_rings(mut self, rings: Rings) -> Self {
+        self.rings = Some(rings);
+        self
+    }
+    
+    /// Generate the surface with `kind` noise, shaded from dark to light versions of the body color
+    pub fn with_surface(self, kind: SurfaceKind, seed: u32) -> Self {
+        let palette = Surface::palette_from_color(self.color);
+        self.with_surface_palette(kind, seed, palette)
+    }
+    
+    /// Generate the surface with `kind` noise, shaded through `palette` from low to high values
+    pub fn with_surface_palette(mut self, kind: SurfaceKind, seed: u32, palette: [[f32; 3]; 3]) -> Self {
+        self.surface = Some(Surface { kind, seed, palette });
+        self
+    }
+    
+    /// Orientation `days_since_epoch` days after J2000. The axis leans toward ecliptic
+    /// longitude 90 degrees, as Earth's does, and the body turns about its local +Y.
+    pub fn orientation(&self, days_since_epoch: f64) -> Quat {
+        let spin = if self.rotation_period == 0.0 {
+            self.rotation_phase
+        } else {
+            // f64 so that millennia of Earth days still land on the right meridian
+            let tau = std::f64::consts::TAU;
+            (self.rotation_phase as f64 + tau * days_since_epoch / self.rotation_period).rem_euclid(tau) as f32
+        };
+        Quat::from_rotation_x(-self.axial_tilt) * Quat::from_rotation_y(spin)
+    }
+    
+    /// Position relative to the parent body (or the origin), `days_since_epoch` days after J2000
+    pub fn local_position(&self, days_since_epoch: f64) -> Vec3 {
         if self.is_sun {
-            [0.0, 0.0, 0.0]
+            Vec3::ZERO
         } else {
-            let x = self.orbit_radius * self.current_angle.cos();
-            let z = self.orbit_radius * self.current_angle.sin();
-            [x, 0.0, z]
+            let mean_anomaly = self.orbit.mean_anomaly_at(days_since_epoch, self.orbital_period);
+            self.orbit.position_at_mean_anomaly(mean_anomaly)
         }
     }
+    
+    /// World position of the point this body orbits
+    pub fn orbit_center(&self, bodies: &[CelestialBody]) -> [f32; 3] {
+        self.parent
+            .and_then(|parent| bodies.get(parent))
+            .map(|parent| parent.get_position())
+            .unwrap_or([0.0, 0.0, 0.0])
+    }
+    
+    /// World position, as of the last `SolarSystem::update`
+    pub fn get_position(&self) -> [f32; 3] {
+        self.position
+    }
 }
 
 pub struct SolarSystem {
     pub bodies: Vec<CelestialBody>,
-    pub time_scale: f32,
+    pub clock: SimulationClock,
 }
 
 impl SolarSystem {
@@ -55,42 +137,200 @@ impl SolarSystem {
         let size_scale = 1.0;  // Planet sizes (not to real scale, for visibility)
         let distance_scale = 1.0;  // Orbital distances
         
-        let bodies = vec![
+        let mut bodies = vec![
             // Sun
-            CelestialBody::new("Sun", 0.15, 0.0, 0.0, [1.0, 0.9, 0.0], true),
+            CelestialBody::new("Sun", 0.15, OrbitalElements::circular(0.0), 0.0, [1.0, 0.9, 0.0], true).spinning(7.25, 25.38, 0.0),
             
-            // Inner planets
-            CelestialBody::new("Mercury", 0.03 * size_scale, 0.5 * distance_scale, 0.04, [0.7, 0.7, 0.7], false),
-            CelestialBody::new("Venus", 0.06 * size_scale, 0.8 * distance_scale, 0.03, [0.9, 0.8, 0.5], false),
-            CelestialBody::new("Earth", 0.06 * size_scale, 1.2 * distance_scale, 0.02, [0.2, 0.5, 0.8], false),
-            CelestialBody::new("Mars", 0.04 * size_scale, 1.6 * distance_scale, 0.015, [0.8, 0.4, 0.2], false),
+            // Inner planets. Eccentricity, angles (degrees) and periods (days) are J2000
+            // values; semi-major axes are compressed for visibility. Spin is given as
+            // obliquity, sidereal rotation period and, for Earth, the sidereal time at J2000.
+            // Surfaces are procedural, seeded per body.
+            CelestialBody::new("Mercury", 0.03 * size_scale, OrbitalElements::from_degrees(0.5 * distance_scale, 0.2056, 7.00, 48.33, 29.12, 174.79), 87.969, [0.7, 0.7, 0.7], false).spinning(0.03, 58.646, 0.0).with_surface(SurfaceKind::Rocky, 1),
+            CelestialBody::new("Venus", 0.06 * size_scale, OrbitalElements::from_degrees(0.8 * distance_scale, 0.0068, 3.39, 76.68, 54.85, 50.45), 224.701, [0.9, 0.8, 0.5], false).spinning(177.36, 243.025, 0.0).with_surface(SurfaceKind::GasGiant, 2),
+            CelestialBody::new("Earth", 0.06 * size_scale, OrbitalElements::from_degrees(1.2 * distance_scale, 0.0167, 0.00, -11.26, 114.21, 357.52), 365.256, [0.2, 0.5, 0.8], false).spinning(23.44, 0.99727, 280.46)
+                .with_surface_palette(SurfaceKind::Rocky, 3, [[0.05, 0.15, 0.45], [0.2, 0.45, 0.2], [0.9, 0.9, 0.9]]),
+            CelestialBody::new("Mars", 0.04 * size_scale, OrbitalElements::from_degrees(1.6 * distance_scale, 0.0934, 1.85, 49.56, 286.50, 19.41), 686.980, [0.8, 0.4, 0.2], false).spinning(25.19, 1.02596, 0.0).with_surface(SurfaceKind::Rocky, 4),
             
-            // Outer planets
-            CelestialBody::new("Jupiter", 0.12 * size_scale, 2.5 * distance_scale, 0.008, [0.8, 0.7, 0.6], false),
-            CelestialBody::new("Saturn", 0.10 * size_scale, 3.5 * distance_scale, 0.006, [0.9, 0.8, 0.6], false),
-            CelestialBody::new("Uranus", 0.08 * size_scale, 4.5 * distance_scale, 0.004, [0.5, 0.8, 0.9], false),
-            CelestialBody::new("Neptune", 0.08 * size_scale, 5.5 * distance_scale, 0.003, [0.3, 0.5, 0.9], false),
+            // Outer planets. Ring radii are in planet radii.
+            CelestialBody::new("Jupiter", 0.12 * size_scale, OrbitalElements::from_degrees(2.5 * distance_scale, 0.0484, 1.30, 100.46, 274.26, 19.65), 4332.59, [0.8, 0.7, 0.6], false).spinning(3.13, 0.41354, 0.0).with_surface(SurfaceKind::GasGiant, 5),
+            CelestialBody::new("Saturn", 0.10 * size_scale, OrbitalElements::from_degrees(3.5 * distance_scale, 0.0539, 2.49, 113.67, 338.94, 317.51), 10759.22, [0.9, 0.8, 0.6], false).spinning(26.73, 0.44401, 0.0).with_surface(SurfaceKind::GasGiant, 6)
+                .with_rings(Rings { inner_radius: 1.24, outer_radius: 2.27, color: [0.85, 0.78, 0.62], opacity: 0.8, receives_shadow: true }),
+            CelestialBody::new("Uranus", 0.08 * size_scale, OrbitalElements::from_degrees(4.5 * distance_scale, 0.0473, 0.77, 74.02, 96.94, 142.27), 30688.5, [0.5, 0.8, 0.9], false).spinning(97.77, 0.71833, 0.0).with_surface(SurfaceKind::GasGiant, 7)
+                .with_rings(Rings { inner_radius: 1.64, outer_radius: 2.0, color: [0.4, 0.4, 0.42], opacity: 0.15, receives_shadow: true }),
+            CelestialBody::new("Neptune", 0.08 * size_scale, OrbitalElements::from_degrees(5.5 * distance_scale, 0.0086, 1.77, 131.78, 273.19, 259.91), 60182.0, [0.3, 0.5, 0.9], false).spinning(28.32, 0.67125, 0.0).with_surface(SurfaceKind::GasGiant, 8)
+                .with_rings(Rings { inner_radius: 1.69, outer_radius: 2.54, color: [0.45, 0.42, 0.4], opacity: 0.08, receives_shadow: true }),
         ];
         
-        Self {
-            bodies,
-            time_scale: 100.0,
-        }
+        // Moons. Angles are relative to the ecliptic; all of these are tidally locked, so
+        // they turn once per orbit.
+        let earth = index_of(&bodies, "Earth");
+        let jupiter = index_of(&bodies, "Jupiter");
+        let saturn = index_of(&bodies, "Saturn");
+        bodies.extend([
+            CelestialBody::new("Moon", 0.016 * size_scale, OrbitalElements::from_degrees(0.12 * distance_scale, 0.0549, 5.15, 125.08, 318.15, 135.27), 27.3217, [0.75, 0.75, 0.72], false).orbiting(earth).spinning(1.54, 27.3217, 0.0).with_surface(SurfaceKind::Rocky, 10),
+            CelestialBody::new("Io", 0.020 * size_scale, OrbitalElements::from_degrees(0.17 * distance_scale, 0.0041, 2.21, 336.0, 84.1, 342.0), 1.769138, [0.9, 0.85, 0.4], false).orbiting(jupiter).spinning(3.1, 1.769138, 0.0).with_surface(SurfaceKind::Rocky, 11),
+            CelestialBody::new("Europa", 0.018 * size_scale, OrbitalElements::from_degrees(0.21 * distance_scale, 0.0090, 2.17, 336.0, 88.9, 171.0), 3.551181, [0.85, 0.8, 0.7], false).orbiting(jupiter).spinning(3.1, 3.551181, 0.0).with_surface(SurfaceKind::Icy, 12),
+            CelestialBody::new("Ganymede", 0.026 * size_scale, OrbitalElements::from_degrees(0.26 * distance_scale, 0.0013, 2.00, 336.0, 192.4, 317.5), 7.154553, [0.6, 0.58, 0.55], false).orbiting(jupiter).spinning(3.1, 7.154553, 0.0).with_surface(SurfaceKind::Icy, 13),
+            CelestialBody::new("Callisto", 0.024 * size_scale, OrbitalElements::from_degrees(0.33 * distance_scale, 0.0074, 1.91, 336.0, 52.6, 181.4), 16.689017, [0.45, 0.42, 0.38], false).orbiting(jupiter).spinning(3.1, 16.689017, 0.0).with_surface(SurfaceKind::Rocky, 14),
+            CelestialBody::new("Titan", 0.025 * size_scale, OrbitalElements::from_degrees(0.32 * distance_scale, 0.0288, 27.7, 169.5, 186.6, 163.3), 15.945, [0.85, 0.65, 0.35], false).orbiting(saturn).spinning(26.7, 15.945, 0.0).with_surface(SurfaceKind::GasGiant, 15),
+        ]);
+        
+        let mut clock = SimulationClock::new(J2000);
+        clock.time_scale = 100.0;
+        
+        let mut system = Self { bodies, clock };
+        system.resolve_positions();
+        system
+    }
+    
+    /// Replace every body, keeping the current date and time scale
+    pub fn set_bodies(&mut self, bodies: Vec<CelestialBody>) {
+        self.bodies = bodies;
+        self.resolve_positions();
     }
     
+    /// Advance the simulation clock by `delta_time` real seconds, move every body
+    /// and extend the trails
     pub fn update(&mut self, delta_time: f32) {
+        let previous = self.clock.days_since_j2000();
+        self.clock.advance(delta_time as f64);
+        self.resolve_positions();
+        self.record_trails(previous);
+    }
+
+    /// Extend every trail up to now. When one frame skips several trail samples, as
+    /// short-period moons do at high time scales or after a long stall, the skipped ones
+    /// are filled in from the orbit since `since`, so trails do not depend on frame rate.
+    /// Deliberate jumps go through `set_date` and `step`, which clear the trails instead.
+    fn record_trails(&mut self, since: f64) {
+        let days = self.clock.days_since_j2000();
+        for index in 0..self.bodies.len() {
+            let length = self.bodies[index].trail_length;
+            let spacing = length / TRAIL_SAMPLES as f64;
+            let elapsed = (days - since).clamp(-length, length);
+            let skipped = if spacing > 0.0 { (elapsed.abs() / spacing) as usize } else { 0 };
+
+            let positions: Vec<(f64, Vec3)> = (1..skipped)
+                .map(|step| {
+                    let sample_days = days - elapsed + elapsed.signum() * spacing * step as f64;
+                    (sample_days, self.world_position_at(index, sample_days))
+                })
+                .collect();
+
+            let body = &mut self.bodies[index];
+            for (sample_days, position) in positions {
+                body.trail.record(sample_days, position, length);
+            }
+            body.trail.record(days, Vec3::from(body.position), length);
+        }
+    }
+
+    /// Jump to `julian_day`; positions depend only on the date, so this is exact
+    pub fn set_date(&mut self, julian_day: f64) {
+        self.clock.set_julian_day(julian_day);
+        self.resolve_positions();
+        self.clear_trails();
+    }
+    
+    /// Move the clock by exactly `days` (negative steps back), even while paused
+    pub fn step(&mut self, days: f64) {
+        self.clock.step(days);
+        self.resolve_positions();
+        self.clear_trails();
+    }
+    
+    pub fn clear_trails(&mut self) {
         for body in &mut self.bodies {
-            body.update(delta_time, self.time_scale);
+            body.trail.clear();
         }
     }
     
+    /// World position of body `index` at `days` since J2000, walking up its parent chain
+    pub fn world_position_at(&self, index: usize, days: f64) -> Vec3 {
+        let mut position = Vec3::ZERO;
+        let mut current = Some(index);
+        // Bounded so a malformed cycle cannot hang the frame
+        for _ in 0..=self.bodies.len() {
+            let Some(body) = current.and_then(|i| self.bodies.get(i)) else { break };
+            position += body.local_position(days);
+            current = body.parent;
+        }
+        position
+    }
+    
+    /// Recompute every body's cached world position for the current date
+    fn resolve_positions(&mut self) {
+        let days = self.clock.days_since_j2000();
+        for index in 0..self.bodies.len() {
+            self.bodies[index].position = self.world_position_at(index, days).to_array();
+        }
+    }
+    
+    /// Simulated days per real second; negative runs the clock backwards
     pub fn set_time_scale(&mut self, scale: f32) {
-        self.time_scale = scale;
+        self.clock.time_scale = scale as f64;
     }
     
     pub fn get_body(&self, index: usize) -> Option<&CelestialBody> {
         self.bodies.get(index)
     }
     
+    /// The body acting as the scene's light source, if any
+    pub fn sun(&self) -> Option<&CelestialBody> {
+        self.bodies.iter().find(|body| body.is_sun)
+    }
+    
     // get_body_mut removed - not used in current implementation
-}
\ No newline at end of file
+}
+
+fn index_of(bodies: &[CelestialBody], name: &str) -> usize {
+    bodies
+        .iter()
+        .position(|body| body.name == name)
+        .expect("built-in body list is missing a parent")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_period_trails_survive_fast_time() {
+        // At 100 days per second, a 60 Hz frame covers about twice Io's trail length
+        let mut system = SolarSystem::new();
+        let io = index_of(&system.bodies, "Io");
+        assert!(100.0 / 60.0 > system.bodies[io].trail_length);
+
+        for _ in 0..10 {
+            system.update(1.0 / 60.0);
+        }
+        assert!(system.bodies[io].trail.samples().count() > 1);
+    }
+
+    #[test]
+    fn a_long_stall_refills_the_trail_along_the_orbit() {
+        let mut system = SolarSystem::new();
+        let earth = index_of(&system.bodies, "Earth");
+        system.update(1.0 / 60.0);
+        system.update(5.0); // 500 days, more than Earth's whole trail
+
+        let days = system.clock.days_since_j2000();
+        let trail = &system.bodies[earth].trail;
+        assert!(trail.samples().count() > TRAIL_SAMPLES / 2);
+        for sample in trail.samples() {
+            assert!(days - sample.days <= system.bodies[earth].trail_length);
+            let expected = system.world_position_at(earth, sample.days);
+            assert!((sample.position - expected).length() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn date_changes_clear_trails() {
+        let mut system = SolarSystem::new();
+        for _ in 0..10 {
+            system.update(1.0 / 60.0);
+        }
+        system.set_date(J2000 + 1000.0);
+        assert!(system.bodies.iter().all(|body| body.trail.samples().count() == 0));
+    }
+}
@@ -1,54 +1,89 @@
-use wasm_bindgen::prelude::*;
-use web_sys::{WebGlBuffer, WebGlProgram, WebGlRenderingContext};
+use crate::backend::{BufferData, BufferHandle, BufferUsage, DrawCall, GraphicsBackend, ProgramHandle, Uniform, VertexAttribute};
+use crate::catalog::{equatorial_to_engine, CatalogEntry};
+use crate::math::{Mat4, Vec3};
+use crate::random::Rng;
+use crate::shapes::geometry::Primitive;
 
 pub struct Starfield {
     stars: Vec<Star>,
-    vertex_buffer: Option<WebGlBuffer>,
+    vertex_buffer: Option<BufferHandle>,
     num_stars: usize,
 }
 
-#[derive(Clone)]
-struct Star {
-    position: [f32; 3], // x, y, z in 3D space
-    brightness: f32,    // 0.0 to 1.0
-    size: f32,          // Point size
+#[derive(Clone, Default)]
+pub struct Star {
+    pub position: [f32; 3],          // x, y, z in 3D space
+    pub brightness: f32,             // 0.0 to 1.0
+    pub size: f32,                   // Point size
+    pub temperature: f32,            // Effective temperature in kelvin, which sets the color
+    pub magnitude: Option<f32>,      // Apparent magnitude, for catalog stars
+    pub name: Option<String>,        // Proper name, e.g. "Vega"
+    pub designation: Option<String>, // e.g. "Alp Lyr" or "HR 7001"
+    pub hip: Option<u32>,            // Hipparcos catalog number
 }
 
+/// Temperature used when a catalog row has no color index; roughly the Sun's
+const DEFAULT_TEMPERATURE: f32 = 5800.0;
+
+/// Share of random stars in each spectral class with its temperature range in kelvin,
+/// loosely following naked-eye star counts: mostly K and G stars, some hot blue B
+/// stars and a scattering of red M stars
+const SPECTRAL_CLASSES: [(f32, f32, f32); 7] = [
+    (0.005, 30_000.0, 45_000.0), // O
+    (0.10, 10_000.0, 30_000.0),  // B
+    (0.15, 7_500.0, 10_000.0),   // A
+    (0.17, 6_000.0, 7_500.0),    // F
+    (0.18, 5_200.0, 6_000.0),    // G
+    (0.26, 3_700.0, 5_200.0),    // K
+    (0.135, 2_400.0, 3_700.0),   // M
+];
+
+/// Faintest and brightest magnitudes that map to the ends of the brightness range
+const FAINT_MAGNITUDE: f32 = 6.5;
+const BRIGHT_MAGNITUDE: f32 = -1.5;
+
 impl Starfield {
-    pub fn new(num_stars: usize, radius: f32) -> Self {
+    /// `num_stars` stars scattered around a sphere of `radius`. The same `seed` always
+    /// gives the same sky.
+    pub fn new(seed: u64, num_stars: usize, radius: f32) -> Self {
+        let mut rng = Rng::new(seed);
         let mut stars = Vec::with_capacity(num_stars);
 
         // Generate random stars distributed in a sphere around the solar system
         for _ in 0..num_stars {
             // Use spherical coordinates for even distribution
-            let theta = js_sys::Math::random() as f32 * 2.0 * std::f32::consts::PI;
-            let phi = js_sys::Math::acos(2.0 * js_sys::Math::random() - 1.0) as f32;
-            let r = radius * (0.8 + 0.2 * js_sys::Math::random() as f32); // Vary distance slightly
+            let theta = rng.next_f32() * 2.0 * std::f32::consts::PI;
+            let phi = rng.range(-1.0, 1.0).acos();
+            let r = radius * rng.range(0.8, 1.0); // Vary distance slightly
 
             let x = r * phi.sin() * theta.cos();
             let y = r * phi.sin() * theta.sin();
             let z = r * phi.cos();
 
             // Random brightness and size for variety - wider range for more dramatic stars
-            let random = js_sys::Math::random() as f32;
+            let random = rng.next_f32();
             let brightness = if random > 0.9 {
                 // 10% of stars are very bright
-                0.8 + 0.2 * js_sys::Math::random() as f32  // 0.8 to 1.0 brightness
+                rng.range(0.8, 1.0)
             } else {
-                0.3 + 0.4 * js_sys::Math::random() as f32  // 0.3 to 0.7 for most stars
+                rng.range(0.3, 0.7)  // Most stars
             };
             
             let size = if random > 0.9 {
                 // Same 10% of stars are also bigger
-                3.0 + 3.0 * js_sys::Math::random() as f32  // 3.0 to 6.0 size for bright stars
+                rng.range(3.0, 6.0)
             } else {
-                1.0 + 2.0 * js_sys::Math::random() as f32  // 1.0 to 3.0 for regular stars
+                rng.range(1.0, 3.0)  // Regular stars
             };
 
+            let temperature = random_temperature(&mut rng);
+
             stars.push(Star {
                 position: [x, y, z],
                 brightness,
                 size,
+                temperature,
+                ..Star::default()
             });
         }
 
@@ -59,102 +94,195 @@ impl Starfield {
         }
     }
 
-    pub fn init_buffers(&mut self, context: &WebGlRenderingContext) -> Result<(), JsValue> {
-        // Create vertex buffer for star positions
-        let buffer = context.create_buffer().ok_or("Failed to create buffer")?;
-        context.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&buffer));
+    /// Real stars at their sky positions on a sphere of `radius`, lined up with the ecliptic
+    /// so the planets move through the zodiac
+    pub fn from_catalog(entries: &[CatalogEntry], radius: f32) -> Self {
+        let stars: Vec<Star> = entries
+            .iter()
+            .map(|entry| {
+                let position = equatorial_to_engine(entry.right_ascension, entry.declination) * radius;
+
+                // 0 at the naked-eye limit, 1 for Sirius; squaring keeps the faint majority small
+                let t = ((FAINT_MAGNITUDE - entry.magnitude) / (FAINT_MAGNITUDE - BRIGHT_MAGNITUDE)).clamp(0.0, 1.0);
+                Star {
+                    position: position.to_array(),
+                    brightness: 0.3 + 0.7 * t,
+                    size: 1.0 + 5.0 * t * t,
+                    temperature: entry.color_index.map_or(DEFAULT_TEMPERATURE, temperature_from_color_index),
+                    magnitude: Some(entry.magnitude),
+                    name: entry.name.clone(),
+                    designation: entry.designation.clone(),
+                    hip: entry.hip,
+                }
+            })
+            .collect();
+
+        Self {
+            num_stars: stars.len(),
+            stars,
+            vertex_buffer: None,
+        }
+    }
+
+    pub fn stars(&self) -> &[Star] {
+        &self.stars
+    }
+
+    /// The star drawn closest to (`x`, `y`) in normalized device coordinates, if any is
+    /// within `max_distance` of it
+    pub fn pick(&self, view_projection: &Mat4, x: f32, y: f32, max_distance: f32) -> Option<usize> {
+        let mut best = None;
+        let mut best_distance = max_distance;
 
-        // Flatten star data: x, y, z, brightness, size for each star
-        let mut vertices = Vec::with_capacity(self.stars.len() * 5);
+        for (index, star) in self.stars.iter().enumerate() {
+            let clip = *view_projection * Vec3::from(star.position).extend(1.0);
+            if clip.w <= 0.0 {
+                continue; // Behind the camera
+            }
+            let ndc = clip.perspective_divide();
+            let distance = ((ndc.x - x).powi(2) + (ndc.y - y).powi(2)).sqrt();
+            if distance <= best_distance {
+                best = Some(index);
+                best_distance = distance;
+            }
+        }
+
+        best
+    }
+
+    pub fn init_buffers(&mut self, backend: &dyn GraphicsBackend) -> Result<(), String> {
+        // Flatten star data: x, y, z, brightness, size, r, g, b for each star
+        let mut vertices = Vec::with_capacity(self.stars.len() * 8);
         for star in &self.stars {
             vertices.push(star.position[0]);
             vertices.push(star.position[1]);
             vertices.push(star.position[2]);
             vertices.push(star.brightness);
             vertices.push(star.size);
+            vertices.extend_from_slice(&blackbody_color(star.temperature));
         }
 
-        unsafe {
-            let vertices_array = js_sys::Float32Array::view(&vertices);
-            context.buffer_data_with_array_buffer_view(
-                WebGlRenderingContext::ARRAY_BUFFER,
-                &vertices_array,
-                WebGlRenderingContext::STATIC_DRAW,
-            );
-        }
-
+        // Create vertex buffer for star data
+        let buffer = backend.create_buffer(BufferData::Vertices(&vertices), BufferUsage::Static)?;
         self.vertex_buffer = Some(buffer);
         Ok(())
     }
 
+    /// Delete the star vertex buffer
+    pub fn dispose(&mut self, backend: &dyn GraphicsBackend) {
+        if let Some(buffer) = self.vertex_buffer.take() {
+            backend.delete_buffer(buffer);
+        }
+    }
+
     pub fn render(
         &self,
-        context: &WebGlRenderingContext,
-        program: &WebGlProgram,
+        backend: &dyn GraphicsBackend,
+        program: ProgramHandle,
         view_matrix: &[f32; 16],
         projection_matrix: &[f32; 16],
-    ) -> Result<(), JsValue> {
-        if let Some(buffer) = &self.vertex_buffer {
-            // Bind the buffer
-            context.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(buffer));
-
-            // Get attribute locations
-            let position_loc = context.get_attrib_location(program, "a_star_position") as u32;
-            let brightness_loc = context.get_attrib_location(program, "a_brightness") as u32;
-            let size_loc = context.get_attrib_location(program, "a_size") as u32;
-
-            // Enable and set up attributes
-            let stride = 5 * 4; // 5 floats * 4 bytes
-
-            // Position attribute (x, y, z)
-            context.vertex_attrib_pointer_with_i32(
-                position_loc,
-                3,
-                WebGlRenderingContext::FLOAT,
-                false,
-                stride,
-                0,
-            );
-            context.enable_vertex_attrib_array(position_loc);
-
-            // Brightness attribute
-            context.vertex_attrib_pointer_with_i32(
-                brightness_loc,
-                1,
-                WebGlRenderingContext::FLOAT,
-                false,
-                stride,
-                3 * 4, // Offset to brightness
-            );
-            context.enable_vertex_attrib_array(brightness_loc);
-
-            // Size attribute
-            context.vertex_attrib_pointer_with_i32(
-                size_loc,
-                1,
-                WebGlRenderingContext::FLOAT,
-                false,
-                stride,
-                4 * 4, // Offset to size
-            );
-            context.enable_vertex_attrib_array(size_loc);
+    ) {
+        if let Some(buffer) = self.vertex_buffer {
+            let stride = 8 * 4; // 8 floats * 4 bytes
+            let attributes = [
+                VertexAttribute { name: "a_star_position", buffer, size: 3, stride, offset: 0, divisor: 0 }, // x, y, z
+                VertexAttribute { name: "a_brightness", buffer, size: 1, stride, offset: 3 * 4, divisor: 0 },
+                VertexAttribute { name: "a_size", buffer, size: 1, stride, offset: 4 * 4, divisor: 0 },
+                VertexAttribute { name: "a_color", buffer, size: 3, stride, offset: 5 * 4, divisor: 0 },      // r, g, b
+            ];
 
             // Set uniforms
-            let view_loc = context.get_uniform_location(program, "u_view_matrix");
-            let proj_loc = context.get_uniform_location(program, "u_projection_matrix");
-
-            context.uniform_matrix4fv_with_f32_array(view_loc.as_ref(), false, view_matrix);
-            context.uniform_matrix4fv_with_f32_array(proj_loc.as_ref(), false, projection_matrix);
+            backend.set_uniform(program, "u_view_matrix", Uniform::Mat4(*view_matrix));
+            backend.set_uniform(program, "u_projection_matrix", Uniform::Mat4(*projection_matrix));
 
             // Draw stars as points
-            context.draw_arrays(WebGlRenderingContext::POINTS, 0, self.num_stars as i32);
+            backend.draw(&DrawCall {
+                program,
+                attributes: &attributes,
+                indices: None,
+                primitive: Primitive::Points,
+                first: 0,
+                count: self.num_stars,
+                instances: 1,
+            });
+        }
+    }
+}
 
-            // Cleanup
-            context.disable_vertex_attrib_array(position_loc);
-            context.disable_vertex_attrib_array(brightness_loc);
-            context.disable_vertex_attrib_array(size_loc);
+/// Pick a spectral class by its share of `SPECTRAL_CLASSES`, then a temperature within it
+fn random_temperature(rng: &mut Rng) -> f32 {
+    let mut roll = rng.next_f32();
+    for (share, coolest, hottest) in SPECTRAL_CLASSES {
+        if roll < share {
+            return rng.range(coolest, hottest);
         }
+        roll -= share;
+    }
+    DEFAULT_TEMPERATURE
+}
 
-        Ok(())
+/// Effective temperature in kelvin from the B−V color index (Ballesteros 2012)
+pub fn temperature_from_color_index(color_index: f32) -> f32 {
+    let bv = color_index.clamp(-0.4, 2.0);
+    4600.0 * (1.0 / (0.92 * bv + 1.7) + 1.0 / (0.92 * bv + 0.62))
+}
+
+/// Approximate color of a blackbody at `kelvin`, normalised so the brightest channel is
+/// 1. A curve fit to CIE blackbody colors (Tanner Helland), good from 1000 K to 40000 K.
+pub fn blackbody_color(kelvin: f32) -> [f32; 3] {
+    let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+    let red = if t <= 66.0 { 255.0 } else { 329.698_73 * (t - 60.0).powf(-0.133_204_76) };
+    let green = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+
+    [red, green, blue].map(|c| (c / 255.0).clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_gives_the_same_sky() {
+        let sky = |seed| {
+            Starfield::new(seed, 500, 100.0)
+                .stars()
+                .iter()
+                .map(|star| (star.position, star.brightness, star.size, star.temperature))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sky(1), sky(1));
+        assert_ne!(sky(1), sky(2));
+    }
+
+    #[test]
+    fn the_sun_comes_out_near_white() {
+        let temperature = temperature_from_color_index(0.65);
+        assert!((temperature - 5780.0).abs() < 100.0, "{}", temperature);
+
+        let [r, g, b] = blackbody_color(temperature);
+        assert_eq!(r, 1.0);
+        assert!(g > 0.9 && b > 0.85, "{:?}", [r, g, b]);
+    }
+
+    #[test]
+    fn color_index_runs_from_blue_to_red() {
+        let hot = blackbody_color(temperature_from_color_index(-0.3));
+        let cool = blackbody_color(temperature_from_color_index(1.8));
+        assert!(hot[2] > hot[0], "{:?}", hot);
+        assert!(cool[0] - cool[2] > 0.4, "{:?}", cool);
+        assert!(temperature_from_color_index(-0.3) > temperature_from_color_index(0.65));
+        assert!(temperature_from_color_index(0.65) > temperature_from_color_index(1.8));
     }
 }
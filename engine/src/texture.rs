@@ -0,0 +1,25 @@
+use web_sys::{HtmlImageElement, ImageData};
+
+/// Pixels to upload. Rows run top to bottom in every case.
+pub enum TextureSource<'a> {
+    Rgba { width: u32, height: u32, pixels: &'a [u8] }, // Four bytes per pixel
+    ImageData(&'a ImageData),
+    Image(&'a HtmlImageElement),                        // Must have finished loading
+}
+
+impl TextureSource<'_> {
+    /// Check raw RGBA pixels match their stated size; browser sources are always valid
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            TextureSource::Rgba { width, height, pixels } => {
+                let expected = (*width as usize).checked_mul(*height as usize).and_then(|n| n.checked_mul(4));
+                match expected {
+                    Some(expected) if expected == pixels.len() => Ok(()),
+                    Some(expected) => Err(format!("Expected {} bytes of RGBA for a {}x{} texture, got {}", expected, width, height, pixels.len())),
+                    None => Err(format!("A {}x{} texture is too large to address", width, height)),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::VecDeque;
+use crate::math::Vec3;
+
+/// Most samples kept per trail, regardless of its length
+pub const TRAIL_SAMPLES: usize = 256;
+
+/// A body's past world positions, oldest first
+#[derive(Clone, Default)]
+pub struct Trail {
+    samples: VecDeque<TrailSample>,
+}
+
+#[derive(Clone, Copy)]
+pub struct TrailSample {
+    pub days: f64, // Days since J2000 when the sample was taken
+    pub position: Vec3,
+}
+
+impl Trail {
+    /// Record `position` at `days` if enough simulated time has passed since the last
+    /// sample, then drop samples older than `length_days`
+    pub fn record(&mut self, days: f64, position: Vec3, length_days: f64) {
+        if length_days <= 0.0 {
+            self.samples.clear();
+            return;
+        }
+
+        let spacing = length_days / TRAIL_SAMPLES as f64;
+        let due = self.samples.back().is_none_or(|last| (days - last.days).abs() >= spacing);
+        if due {
+            self.samples.push_back(TrailSample { days, position });
+        }
+
+        // Age is measured both ways so trails also work while time runs backwards
+        while self.samples.front().is_some_and(|oldest| (days - oldest.days).abs() > length_days)
+            || self.samples.len() > TRAIL_SAMPLES
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &TrailSample> {
+        self.samples.iter()
+    }
+}
@@ -0,0 +1,138 @@
+//! Checks on the calls the engine makes, recorded by `RecordingBackend` without drawing anything.
+
+use std::collections::HashSet;
+use graphics_engine::backend::recording::{Command, CommandLog};
+use graphics_engine::backend::{RecordingBackend, ShaderKind};
+use graphics_engine::shapes::geometry::Primitive;
+use graphics_engine::GraphicsEngine;
+
+fn engine() -> (GraphicsEngine, CommandLog) {
+    let backend = RecordingBackend::new();
+    let log = backend.log();
+    let engine = GraphicsEngine::with_backend(Box::new(backend), 320, 240)
+        .expect("engine should build on the recording backend");
+    log.clear();
+    (engine, log)
+}
+
+#[test]
+fn render_draws_the_triangle_once() {
+    let (engine, log) = engine();
+    engine.render();
+
+    let draws = log.draws();
+    assert_eq!(draws.len(), 1);
+    assert_eq!(draws[0].primitive, Primitive::Triangles);
+    assert_eq!(draws[0].count, 3);
+    assert!(draws[0].indexed);
+}
+
+#[test]
+fn wireframe_draws_unique_edges_as_lines() {
+    let (mut engine, log) = engine();
+    engine.set_wireframe_mode(true);
+    engine.render_cube();
+
+    let draws = log.draws();
+    assert_eq!(draws.len(), 1);
+    assert_eq!(draws[0].primitive, Primitive::Lines);
+    assert_eq!(draws[0].count, 5 * 2); // Four sides and the diagonal
+}
+
+#[test]
+fn solar_system_draws_each_body_once() {
+    let (engine, log) = engine();
+    engine.render_solar_system();
+
+    let draws = log.draws();
+    let bodies = draws.iter().filter(|draw| draw.shader == ShaderKind::Lit).count();
+    assert_eq!(bodies, engine.get_planet_count());
+}
+
+#[test]
+fn solar_system_draws_the_starfield_as_one_batch_of_points() {
+    let (engine, log) = engine();
+    engine.render_solar_system();
+
+    let points: Vec<_> = log.draws().into_iter().filter(|draw| draw.primitive == Primitive::Points).collect();
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].shader, ShaderKind::Starfield);
+    assert_eq!(points[0].count, engine.get_star_count());
+}
+
+#[test]
+fn orbits_are_drawn_as_one_instanced_loop() {
+    let (mut engine, log) = engine();
+    let orbit_draws = || -> Vec<_> { log.draws().into_iter().filter(|draw| draw.shader == ShaderKind::Orbit).collect() };
+
+    engine.render_solar_system();
+    let draws = orbit_draws();
+    assert_eq!(draws.len(), 1);
+    assert_eq!(draws[0].primitive, Primitive::LineLoop);
+    let shown = draws[0].instances;
+    assert!(shown > 1);
+
+    // Hidden orbits are left out of the instance data
+    log.clear();
+    engine.set_orbit_visible(1, false);
+    engine.render_solar_system();
+    assert_eq!(orbit_draws()[0].instances, shown - 1);
+}
+
+#[test]
+fn orbits_are_drawn_one_by_one_without_instancing() {
+    let backend = RecordingBackend::without_instancing();
+    let log = backend.log();
+    let engine = GraphicsEngine::with_backend(Box::new(backend), 320, 240).unwrap();
+    log.clear();
+    engine.render_solar_system();
+
+    let draws = log.draws();
+    assert!(draws.iter().all(|draw| draw.instances == 1));
+    assert!(!draws.iter().any(|draw| draw.shader == ShaderKind::Orbit));
+
+    // As many loops as the instanced path draws instances
+    let (instanced_engine, instanced_log) = self::engine();
+    instanced_engine.render_solar_system();
+    let instanced = instanced_log.draws().into_iter().find(|draw| draw.shader == ShaderKind::Orbit).unwrap();
+    let orbits = draws.iter().filter(|draw| draw.shader == ShaderKind::Line && draw.primitive == Primitive::LineLoop);
+    assert_eq!(orbits.count(), instanced.instances);
+}
+
+#[test]
+fn solar_system_leaves_blending_off_and_depth_writes_on() {
+    let (engine, log) = engine();
+    engine.render_solar_system();
+
+    let state = log.state();
+    assert!(!state.blending);
+    assert!(state.depth_write);
+    assert_eq!(log.commands().last(), Some(&Command::SetBlending(false)));
+}
+
+#[test]
+fn dispose_frees_every_buffer_texture_and_program() {
+    let backend = RecordingBackend::new();
+    let log = backend.log();
+    let mut engine = GraphicsEngine::with_backend(Box::new(backend), 320, 240).unwrap();
+    engine.render_solar_system();
+    engine.dispose();
+
+    let mut buffers = HashSet::new();
+    let mut textures = HashSet::new();
+    let mut programs = HashSet::new();
+    for command in log.commands() {
+        match command {
+            Command::CreateBuffer { buffer, .. } => assert!(buffers.insert(buffer)),
+            Command::DeleteBuffer(buffer) => assert!(buffers.remove(&buffer), "{buffer:?} deleted twice"),
+            Command::CreateTexture { texture, .. } => assert!(textures.insert(texture)),
+            Command::DeleteTexture(texture) => assert!(textures.remove(&texture), "{texture:?} deleted twice"),
+            Command::CreateProgram { program, .. } => assert!(programs.insert(program)),
+            Command::DeleteProgram(program) => assert!(programs.remove(&program), "{program:?} deleted twice"),
+            _ => {}
+        }
+    }
+    assert!(buffers.is_empty(), "leaked buffers: {buffers:?}");
+    assert!(textures.is_empty(), "leaked textures: {textures:?}");
+    assert!(programs.is_empty(), "leaked programs: {programs:?}");
+}
@@ -0,0 +1,193 @@
+//! Golden-image tests: named scenes are rendered on the software backend and compared
+//! with the PNGs in `engine/tests/golden`. Run with `UPDATE_GOLDEN=1` to rewrite the references
+//! after an intended change. On a mismatch the actual image and a diff, with differing
+//! pixels in red, are written under the target directory.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use graphics_engine::backend::SoftwareBackend;
+use graphics_engine::GraphicsEngine;
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 120;
+
+/// Largest colour difference, as a fraction of the largest possible, that still looks the same
+const PIXEL_THRESHOLD: f32 = 0.1;
+
+/// Share of pixels allowed to differ, for platform differences in `sin`, `exp` and friends
+const MAX_DIFFERING: f32 = 0.002;
+
+/// 2000-03-20, shortly after the equinox
+const SCENE_DATE: f64 = 2_451_623.5;
+
+struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>, // RGBA, top row first
+}
+
+fn engine() -> GraphicsEngine {
+    GraphicsEngine::with_backend(Box::new(SoftwareBackend::new(WIDTH, HEIGHT)), WIDTH, HEIGHT)
+        .expect("engine should build on the software backend")
+}
+
+fn capture(engine: &GraphicsEngine) -> Image {
+    Image { width: WIDTH, height: HEIGHT, pixels: engine.read_pixels() }
+}
+
+fn solar_system_engine() -> GraphicsEngine {
+    let mut engine = engine();
+    engine.set_date(SCENE_DATE);
+    engine.set_camera_distance(3.0);
+    engine.set_camera_angles(-0.45, 0.6);
+    engine
+}
+
+#[test]
+fn triangle() {
+    let mut engine = engine();
+    engine.set_color(1.0, 0.6, 0.3);
+    engine.set_rotation(0.3);
+    engine.render();
+    assert_matches_golden("triangle", &capture(&engine));
+}
+
+#[test]
+fn rectangle() {
+    let mut engine = engine();
+    engine.set_color(0.4, 0.8, 1.0);
+    engine.render_cube();
+    assert_matches_golden("rectangle", &capture(&engine));
+}
+
+#[test]
+fn solar_system() {
+    let engine = solar_system_engine();
+    engine.render_solar_system();
+    assert_matches_golden("solar_system", &capture(&engine));
+}
+
+#[test]
+fn solar_system_wireframe() {
+    let mut engine = solar_system_engine();
+    engine.set_wireframe_mode(true);
+    engine.render_solar_system();
+    assert_matches_golden("solar_system_wireframe", &capture(&engine));
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("engine/tests/golden").join(format!("{name}.png"))
+}
+
+fn output_path(name: &str, kind: &str) -> PathBuf {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&directory).expect("failed to create the golden output directory");
+    directory.join(format!("{name}.{kind}.png"))
+}
+
+fn assert_matches_golden(name: &str, actual: &Image) {
+    let path = golden_path(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).expect("failed to create the golden directory");
+        write_png(&path, actual);
+        return;
+    }
+
+    let expected = read_png(&path).unwrap_or_else(|error| {
+        panic!("{}: {error}; run with UPDATE_GOLDEN=1 to create it", path.display())
+    });
+    assert!(
+        expected.width == actual.width && expected.height == actual.height,
+        "{name}: rendered {}x{}, reference is {}x{}",
+        actual.width,
+        actual.height,
+        expected.width,
+        expected.height,
+    );
+
+    let (differing, diff) = compare(&expected, actual);
+    let share = differing as f32 / (actual.width * actual.height) as f32;
+    if share > MAX_DIFFERING {
+        let actual_path = output_path(name, "actual");
+        let diff_path = output_path(name, "diff");
+        write_png(&actual_path, actual);
+        write_png(&diff_path, &diff);
+        panic!(
+            "{name}: {differing} pixels ({:.2}%) differ from {}\n  actual: {}\n  diff:   {}",
+            share * 100.0,
+            path.display(),
+            actual_path.display(),
+            diff_path.display(),
+        );
+    }
+}
+
+/// Count pixels that look different and draw them in red over a faded copy of `expected`
+fn compare(expected: &Image, actual: &Image) -> (usize, Image) {
+    let mut differing = 0;
+    let mut pixels = Vec::with_capacity(expected.pixels.len());
+
+    for (a, b) in expected.pixels.chunks_exact(4).zip(actual.pixels.chunks_exact(4)) {
+        if color_delta(a, b) > PIXEL_THRESHOLD {
+            differing += 1;
+            pixels.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let gray = (luma(a) * 0.3 * 255.0 + 255.0 * 0.7) as u8;
+            pixels.extend_from_slice(&[gray, gray, gray, 255]);
+        }
+    }
+
+    (differing, Image { width: expected.width, height: expected.height, pixels })
+}
+
+fn luma(pixel: &[u8]) -> f32 {
+    (0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32) / 255.0
+}
+
+/// Perceptual distance in YIQ space, weighted as in `pixelmatch`, from 0 (same) to 1.
+/// Both pixels are composited over white first so transparent ones compare sensibly.
+fn color_delta(a: &[u8], b: &[u8]) -> f32 {
+    let over_white = |pixel: &[u8]| {
+        let alpha = pixel[3] as f32 / 255.0;
+        [0, 1, 2].map(|c| 255.0 + (pixel[c] as f32 - 255.0) * alpha)
+    };
+    let yiq = |[r, g, b]: [f32; 3]| {
+        [
+            r * 0.298_895_9 + g * 0.586_622_5 + b * 0.114_181_6,
+            r * 0.595_978 - g * 0.274_176_5 - b * 0.321_801_5,
+            r * 0.211_470_2 - g * 0.522_617_2 + b * 0.311_147,
+        ]
+    };
+
+    let [y1, i1, q1] = yiq(over_white(a));
+    let [y2, i2, q2] = yiq(over_white(b));
+    let (dy, di, dq) = (y1 - y2, i1 - i2, q1 - q2);
+    (0.5053 * dy * dy + 0.299 * di * di + 0.1957 * dq * dq) / 35_215.0
+}
+
+fn read_png(path: &Path) -> Result<Image, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut decoder = png::Decoder::new(std::io::BufReader::new(file));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::ALPHA);
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut pixels = vec![0; reader.output_buffer_size().ok_or("image too large")?];
+    let info = reader.next_frame(&mut pixels).map_err(|e| e.to_string())?;
+
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(format!("expected 8-bit RGBA, found {:?} {:?}", info.color_type, info.bit_depth));
+    }
+    pixels.truncate(info.buffer_size());
+    Ok(Image { width: info.width, height: info.height, pixels })
+}
+
+fn write_png(path: &Path, image: &Image) {
+    let file = File::create(path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&image.pixels))
+        .unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+}
@@ -7,6 +7,19 @@ declare global {
   }
 }
 
+// The engine's time scale is simulated days per real second. The slider is logarithmic,
+// from about 15 minutes to about 10 months per second.
+const DEFAULT_TIME_SCALE = 10;
+const MIN_TIME_SCALE_LOG = -2;
+const MAX_TIME_SCALE_LOG = 2.5;
+
+function formatTimeScale(daysPerSecond: number): string {
+  if (daysPerSecond < 1) {
+    return `${(daysPerSecond * 24).toFixed(1)} hours/s`;
+  }
+  return `${daysPerSecond.toFixed(daysPerSecond < 10 ? 1 : 0)} days/s`;
+}
+
 export default function SolarSystem() {
   const canvasRef = useRef<HTMLCanvasElement>(null);
   const engineRef = useRef<any>(null);
@@ -14,7 +27,7 @@ export default function SolarSystem() {
   const lastTimeRef = useRef<number>(0);
 
   const [status, setStatus] = useState("Loading...");
-  const [timeScale, setTimeScale] = useState(100);
+  const [timeScale, setTimeScale] = useState(DEFAULT_TIME_SCALE);
   const [isAnimating, setIsAnimating] = useState(true);
   const [wireframeMode, setWireframeMode] = useState(false);
   const [cameraDistance, setCameraDistance] = useState(1.5);
@@ -102,9 +115,9 @@ export default function SolarSystem() {
 
         if (canvasRef.current && GraphicsEngine) {
           console.log('Creating GraphicsEngine instance...');
-          const engine = new GraphicsEngine("solar-canvas");
+          const engine = new GraphicsEngine("solar-canvas", true);
           engineRef.current = engine;
-          console.log('Engine created successfully');
+          console.log('Engine created successfully on', engine.get_backend_name());
 
           // Set initial values
           engine.set_background_color(0.02, 0.02, 0.05, 1.0);
@@ -285,15 +298,15 @@ export default function SolarSystem() {
                   <h4 style={{ margin: "0 0 10px 0", fontSize: "14px" }}>⏱️ Time Controls</h4>
                   <div style={{ marginBottom: "10px" }}>
                     <label style={{ display: "block", marginBottom: "5px", fontSize: "12px" }}>
-                      Speed: {timeScale.toFixed(1)}x
+                      Speed: {formatTimeScale(timeScale)}
                     </label>
                     <input
                       type="range"
-                      min="0"
-                      max="200"
-                      step="1"
-                      value={timeScale}
-                      onInput={(e) => setTimeScale(parseFloat(e.currentTarget.value))}
+                      min={MIN_TIME_SCALE_LOG}
+                      max={MAX_TIME_SCALE_LOG}
+                      step="0.05"
+                      value={Math.log10(timeScale)}
+                      onInput={(e) => setTimeScale(Math.pow(10, parseFloat(e.currentTarget.value)))}
                       style={{ width: "100%" }}
                     />
                   </div>
@@ -405,7 +418,7 @@ export default function SolarSystem() {
 
                   <button
                     onClick={() => {
-                      setTimeScale(100);
+                      setTimeScale(DEFAULT_TIME_SCALE);
                       setCameraDistance(5);
                       setCameraAngles({ x: -0.3, y: 0 });
                       setWireframeMode(false);